@@ -0,0 +1,283 @@
+use std::time::Duration;
+
+use bevy::{input::common_conditions::input_just_pressed, prelude::*};
+use bevy_mod_picking::prelude::*;
+use bevy_picking_core::events::PointerEvent;
+use bevy_pkv::PkvStore;
+use serde::{Deserialize, Serialize};
+
+use crate::big_number::BigNumber;
+use crate::idle_gains::{Ledger, ResourceKind};
+use crate::overclock::NodeOverclock;
+use crate::progress::Progress;
+use crate::save_slots::is_typing;
+use crate::settings::Settings;
+use crate::upgrade_shop::NodeUpgrades;
+use crate::{InheritedBlockStatus, NodeCurrencyGain, NodeManualBlockToggle, SelfBlockStatus};
+
+/// Threshold of a new coins rule.
+const DEFAULT_RULE_COINS: f64 = 10f64;
+
+pub struct AutomationPlugin;
+
+impl Plugin for AutomationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ActivateNode>()
+            .add_system(
+                toggle_automation
+                    .run_if(input_just_pressed(KeyCode::C))
+                    .run_if(not(is_typing)),
+            )
+            .add_system(pointer_activate.in_set(NodeActivation))
+            .add_system(run_auto_clickers.in_set(NodeActivation))
+            .add_system(run_node_rules.in_set(NodeActivation));
+    }
+}
+
+/// A node is clicked, by the player or by its auto-clicker.
+pub struct ActivateNode(pub Entity);
+
+/// Systems sending [`ActivateNode`], node reactions run after them.
+#[derive(SystemSet, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct NodeActivation;
+
+/// Time until the next click of a node auto-clicker, see [`NodeUpgrades::auto_click_interval`].
+#[derive(Component, Default)]
+pub struct AutoClicker {
+    timer: Timer,
+}
+
+impl AutoClicker {
+    /// Counts down `delta`, whether it clicks its node now: once `interval` elapsed,
+    /// as soon as the node [`is_ready_for_auto_click`].
+    pub fn tick(&mut self, delta: Duration, interval: f32, is_ready: bool) -> bool {
+        self.timer.set_duration(Duration::from_secs_f32(interval));
+        self.timer.tick(delta);
+        if self.timer.finished() && is_ready {
+            self.timer.reset();
+            return true;
+        }
+        false
+    }
+}
+
+/// Seconds between clicks of the auto-clicker of a node, nodes with a [`NodeRule`] are left to it.
+pub fn auto_click_interval(upgrades: &NodeUpgrades, rule: &NodeRule) -> Option<f32> {
+    if rule.condition.is_some() {
+        return None;
+    }
+    upgrades.auto_click_interval()
+}
+
+/// Ready nodes are clicked, blockers only to unblock them.
+pub fn is_ready_for_auto_click(
+    is_finished: bool,
+    is_inherited_blocked: bool,
+    manual_blocked: Option<bool>,
+) -> bool {
+    is_finished && !is_inherited_blocked && manual_blocked.unwrap_or(true)
+}
+
+/// When automation activates a node, edited in the node panel and persisted with it.
+///
+/// Gains and saves are clicked when ready while the condition holds.
+/// Blockers are unblocked while it holds, and blocked again when it doesn't.
+#[derive(Component, Reflect, Serialize, Deserialize, Clone, Debug, Default)]
+pub struct NodeRule {
+    pub condition: Option<RuleCondition>,
+}
+
+#[derive(Reflect, FromReflect, Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum RuleCondition {
+    CoinsAbove(BigNumber),
+    CoinsBelow(BigNumber),
+    AllGainsBlocked,
+    AnyGainReady,
+}
+
+/// State of the map rules are checked against.
+pub struct RuleFacts {
+    coins: BigNumber,
+    all_gains_blocked: bool,
+    any_gain_ready: bool,
+}
+
+impl RuleFacts {
+    /// From the coins owned, and whether each gain is blocked and its timer finished.
+    pub fn new(coins: BigNumber, gains: impl Iterator<Item = (bool, bool)>) -> Self {
+        let gains: Vec<(bool, bool)> = gains.collect();
+        RuleFacts {
+            coins,
+            all_gains_blocked: gains.iter().all(|(is_blocked, _)| *is_blocked),
+            any_gain_ready: gains
+                .iter()
+                .any(|(is_blocked, is_finished)| !is_blocked && *is_finished),
+        }
+    }
+}
+
+impl RuleCondition {
+    /// Cycles through conditions in the rule editor, `None` removes the rule.
+    pub fn next(condition: Option<RuleCondition>) -> Option<RuleCondition> {
+        let coins = BigNumber::from(DEFAULT_RULE_COINS);
+        match condition {
+            None => Some(RuleCondition::CoinsAbove(coins)),
+            Some(RuleCondition::CoinsAbove(coins)) => Some(RuleCondition::CoinsBelow(coins)),
+            Some(RuleCondition::CoinsBelow(_)) => Some(RuleCondition::AllGainsBlocked),
+            Some(RuleCondition::AllGainsBlocked) => Some(RuleCondition::AnyGainReady),
+            Some(RuleCondition::AnyGainReady) => None,
+        }
+    }
+
+    pub fn threshold(self) -> Option<BigNumber> {
+        match self {
+            RuleCondition::CoinsAbove(coins) | RuleCondition::CoinsBelow(coins) => Some(coins),
+            RuleCondition::AllGainsBlocked | RuleCondition::AnyGainReady => None,
+        }
+    }
+
+    /// Multiplies the coins threshold by `factor`, keeping it at least 1.
+    pub fn scaled(self, factor: f64) -> RuleCondition {
+        let scale = |coins: BigNumber| {
+            let scaled = coins * factor;
+            if scaled < BigNumber::ONE {
+                BigNumber::ONE
+            } else {
+                scaled
+            }
+        };
+        match self {
+            RuleCondition::CoinsAbove(coins) => RuleCondition::CoinsAbove(scale(coins)),
+            RuleCondition::CoinsBelow(coins) => RuleCondition::CoinsBelow(scale(coins)),
+            other => other,
+        }
+    }
+
+    /// What the condition checks, for `coins` formatted by the caller.
+    pub fn describe(self, coins: impl Fn(BigNumber) -> String) -> String {
+        match self {
+            RuleCondition::CoinsAbove(threshold) => format!("coins > {}", coins(threshold)),
+            RuleCondition::CoinsBelow(threshold) => format!("coins < {}", coins(threshold)),
+            RuleCondition::AllGainsBlocked => "all gains are blocked".to_string(),
+            RuleCondition::AnyGainReady => "a gain is ready".to_string(),
+        }
+    }
+
+    fn holds(self, facts: &RuleFacts) -> bool {
+        match self {
+            RuleCondition::CoinsAbove(threshold) => facts.coins > threshold,
+            RuleCondition::CoinsBelow(threshold) => facts.coins < threshold,
+            RuleCondition::AllGainsBlocked => facts.all_gains_blocked,
+            RuleCondition::AnyGainReady => facts.any_gain_ready,
+        }
+    }
+
+    /// Whether the rule clicks its node, ready and not blocked by others.
+    ///
+    /// `self_blocked` is whether a blocker blocks, `None` for other nodes.
+    pub fn wants_click(self, facts: &RuleFacts, self_blocked: Option<bool>) -> bool {
+        let holds = self.holds(facts);
+        match self_blocked {
+            // Blocked while it doesn't hold, so a click is needed when both are equal.
+            Some(is_blocked) => is_blocked == holds,
+            None => holds,
+        }
+    }
+}
+
+fn toggle_automation(mut settings: ResMut<Settings>, mut pkv: ResMut<PkvStore>) {
+    settings.automation_enabled = !settings.automation_enabled;
+    info!("automation enabled: {}", settings.automation_enabled);
+    settings.persist(&mut pkv);
+}
+
+fn pointer_activate(
+    mut events: EventReader<PointerEvent<Down>>,
+    mut activate: EventWriter<ActivateNode>,
+) {
+    for event in events.iter() {
+        if event.event.button == PointerButton::Primary {
+            activate.send(ActivateNode(event.target));
+        }
+    }
+}
+
+/// Clicks nodes with an [`auto_click_interval`], see [`AutoClicker::tick`].
+///
+/// Overclocks are never auto-clicked,
+/// even with an auto-click level bought before it was removed from their upgrades.
+fn run_auto_clickers(
+    time: Res<Time>,
+    settings: Res<Settings>,
+    mut activate: EventWriter<ActivateNode>,
+    mut q_nodes: Query<
+        (
+            Entity,
+            &NodeUpgrades,
+            &NodeRule,
+            &mut AutoClicker,
+            &Progress,
+            &InheritedBlockStatus,
+            Option<&NodeManualBlockToggle>,
+        ),
+        Without<NodeOverclock>,
+    >,
+) {
+    if !settings.automation_enabled {
+        return;
+    }
+    for (e, upgrades, rule, mut clicker, progress, status, manual) in q_nodes.iter_mut() {
+        let Some(interval) = auto_click_interval(upgrades, rule) else {
+            continue;
+        };
+        let is_ready = is_ready_for_auto_click(
+            progress.timer.finished(),
+            status.is_blocked,
+            manual.map(|manual| manual.is_blocked),
+        );
+        if clicker.tick(time.delta(), interval, is_ready) {
+            activate.send(ActivateNode(e));
+        }
+    }
+}
+
+/// Clicks nodes whose [`NodeRule`] holds.
+///
+/// Overclocks don't take rules anymore, but older saves may still have some: they are ignored.
+fn run_node_rules(
+    settings: Res<Settings>,
+    ledger: Res<Ledger>,
+    mut activate: EventWriter<ActivateNode>,
+    q_nodes: Query<
+        (
+            Entity,
+            &NodeRule,
+            &Progress,
+            &SelfBlockStatus,
+            &InheritedBlockStatus,
+            Option<&NodeManualBlockToggle>,
+            Option<&NodeCurrencyGain>,
+        ),
+        Without<NodeOverclock>,
+    >,
+) {
+    if !settings.automation_enabled {
+        return;
+    }
+    let gains = q_nodes
+        .iter()
+        .filter(|(.., gain)| gain.is_some())
+        .map(|(_, _, progress, _, status, _, _)| (status.is_blocked, progress.timer.finished()));
+    let facts = RuleFacts::new(ledger.get(ResourceKind::Coins), gains);
+    for (e, rule, progress, self_status, status, manual, _) in q_nodes.iter() {
+        let Some(condition) = rule.condition else {
+            continue;
+        };
+        if !progress.timer.finished() || status.is_blocked {
+            continue;
+        }
+        if condition.wants_click(&facts, manual.map(|_| self_status.is_blocked)) {
+            activate.send(ActivateNode(e));
+        }
+    }
+}
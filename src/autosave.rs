@@ -0,0 +1,256 @@
+use bevy::{
+    app::AppExit, input::common_conditions::input_just_pressed, prelude::*,
+    window::WindowCloseRequested,
+};
+use bevy_pkv::PkvStore;
+
+use crate::offline_progress::format_duration;
+use crate::persisted_game::{now_since2023, EventSave, LastSave, SaveSnapshot, SaveWriter};
+use crate::save_slots::{is_typing, SaveSlots};
+use crate::settings::Settings;
+
+/// Intervals cycled through with the autosave key, 0 disables it.
+const AUTOSAVE_INTERVALS_SECONDS: [f32; 5] = [15f32, 30f32, 60f32, 300f32, 0f32];
+
+pub struct AutosavePlugin;
+
+impl Plugin for AutosavePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<AutosaveTimer>()
+            .add_startup_system(setup_indicator)
+            .add_system(autosave)
+            .add_system(
+                cycle_autosave_interval
+                    .run_if(input_just_pressed(KeyCode::A))
+                    .run_if(not(is_typing)),
+            )
+            .add_system(update_indicator)
+            .add_system(save_on_exit.in_base_set(CoreSet::Last));
+        #[cfg(target_arch = "wasm32")]
+        {
+            visibility::listen();
+            app.add_startup_system(visibility::recover_pending)
+                .add_system(visibility::cache_latest);
+        }
+    }
+}
+
+#[derive(Resource)]
+struct AutosaveTimer(Timer);
+
+impl Default for AutosaveTimer {
+    fn default() -> Self {
+        Self(Timer::from_seconds(0f32, TimerMode::Repeating))
+    }
+}
+
+#[derive(Component)]
+struct AutosaveText;
+
+fn autosave(
+    time: Res<Time>,
+    settings: Res<Settings>,
+    slots: Res<SaveSlots>,
+    mut timer: ResMut<AutosaveTimer>,
+    mut event_save: EventWriter<EventSave>,
+) {
+    if settings.autosave_interval_seconds <= 0f32 {
+        return;
+    }
+    if timer.0.duration().as_secs_f32() != settings.autosave_interval_seconds {
+        timer.0 = Timer::from_seconds(settings.autosave_interval_seconds, TimerMode::Repeating);
+    }
+    if timer.0.tick(time.delta()).just_finished() {
+        event_save.send(EventSave {
+            slot: slots.current,
+        });
+    }
+}
+
+fn cycle_autosave_interval(mut settings: ResMut<Settings>, mut pkv: ResMut<PkvStore>) {
+    let current = AUTOSAVE_INTERVALS_SECONDS
+        .iter()
+        .position(|interval| *interval == settings.autosave_interval_seconds);
+    settings.autosave_interval_seconds = AUTOSAVE_INTERVALS_SECONDS
+        [current.map_or(0, |i| (i + 1) % AUTOSAVE_INTERVALS_SECONDS.len())];
+    settings.persist(&mut pkv);
+}
+
+/// Saves synchronously, as there won't be another frame to handle an [`EventSave`].
+fn save_on_exit(
+    snapshot: SaveSnapshot,
+    mut writer: SaveWriter,
+    slots: Res<SaveSlots>,
+    mut exit: EventReader<AppExit>,
+    mut close_requested: EventReader<WindowCloseRequested>,
+) {
+    // Read both, to not save again for the same exit next frame.
+    let is_exiting = exit.iter().count() > 0;
+    let is_closing = close_requested.iter().count() > 0;
+    if is_exiting || is_closing {
+        // Failures are reported by the writer, there is nothing more to do on exit.
+        let _ = writer.save(&snapshot, slots.current);
+    }
+}
+
+fn setup_indicator(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                font_size: 20.0,
+                color: Color::GRAY,
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                bottom: Val::Px(5.0),
+                right: Val::Px(15.0),
+                ..default()
+            },
+            ..default()
+        }),
+        AutosaveText,
+    ));
+}
+
+fn update_indicator(
+    time: Res<Time>,
+    last_save: Res<LastSave>,
+    settings: Res<Settings>,
+    mut refresh: Local<Timer>,
+    mut q_text: Query<&mut Text, With<AutosaveText>>,
+) {
+    // Text shows seconds, no need to update it every frame.
+    refresh.tick(time.delta());
+    if !refresh.finished() && !last_save.is_changed() && !settings.is_changed() {
+        return;
+    }
+    *refresh = Timer::from_seconds(1f32, TimerMode::Once);
+    let saved = match last_save.time_since2023 {
+        Some(time) => format!(
+            "saved {} ago",
+            format_duration((now_since2023() - time).max(0f64) as f32)
+        ),
+        None => "not saved yet".to_string(),
+    };
+    let autosave = if settings.autosave_interval_seconds > 0f32 {
+        format!(
+            "autosave every {} (A)",
+            format_duration(settings.autosave_interval_seconds)
+        )
+    } else {
+        "autosave off (A)".to_string()
+    };
+    for mut text in q_text.iter_mut() {
+        text.sections[0].value = format!("{} - {}", saved, autosave);
+    }
+}
+
+/// Hidden tabs don't run frames and may be discarded without any exit event,
+/// so the page listens to `visibilitychange` and writes a recent snapshot itself,
+/// recovered into its slot on next startup.
+#[cfg(target_arch = "wasm32")]
+mod visibility {
+    use std::sync::Mutex;
+
+    use bevy::prelude::*;
+    use bevy_pkv::PkvStore;
+    use serde::{Deserialize, Serialize};
+    use wasm_bindgen::{closure::Closure, JsCast};
+
+    use crate::persisted_game::{save_key, Save, SaveSnapshot};
+    use crate::save_slots::{SaveSlots, SlotId};
+
+    const PENDING_SAVE_KEY: &str = "sidleffect_pending_save";
+
+    /// Json of the latest [`PendingSave`], ready to be written when the page gets hidden.
+    static LATEST: Mutex<Option<String>> = Mutex::new(None);
+
+    #[derive(Serialize, Deserialize)]
+    struct PendingSave {
+        slot: SlotId,
+        save: Save,
+    }
+
+    pub fn listen() {
+        let Some(document) = web_sys::window().and_then(|window| window.document()) else {
+            return;
+        };
+        let listened = document.clone();
+        let on_change = Closure::<dyn FnMut()>::new(move || {
+            if listened.visibility_state() != web_sys::VisibilityState::Hidden {
+                return;
+            }
+            let Some(latest) = LATEST.lock().ok().and_then(|latest| latest.clone()) else {
+                return;
+            };
+            if let Some(storage) = web_sys::window().and_then(|w| w.local_storage().ok().flatten())
+            {
+                let _ = storage.set_item(PENDING_SAVE_KEY, &latest);
+            }
+        });
+        if document
+            .add_event_listener_with_callback(
+                "visibilitychange",
+                on_change.as_ref().unchecked_ref(),
+            )
+            .is_err()
+        {
+            error!("could not listen to visibility changes");
+        }
+        // The listener lives as long as the page.
+        on_change.forget();
+    }
+
+    pub fn cache_latest(
+        time: Res<Time>,
+        snapshot: SaveSnapshot,
+        slots: Res<SaveSlots>,
+        mut refresh: Local<Timer>,
+    ) {
+        refresh.tick(time.delta());
+        if !refresh.finished() {
+            return;
+        }
+        *refresh = Timer::from_seconds(1f32, TimerMode::Once);
+        let (save, _) = snapshot.snapshot();
+        if save.nodes.is_empty() {
+            return;
+        }
+        let pending = PendingSave {
+            slot: slots.current,
+            save,
+        };
+        if let (Ok(json), Ok(mut latest)) = (serde_json::to_string(&pending), LATEST.lock()) {
+            *latest = Some(json);
+        }
+    }
+
+    /// Moves a save written while hidden into its slot, if it's more recent.
+    pub fn recover_pending(mut pkv: ResMut<PkvStore>) {
+        let Some(storage) = web_sys::window().and_then(|w| w.local_storage().ok().flatten()) else {
+            return;
+        };
+        let Ok(Some(json)) = storage.get_item(PENDING_SAVE_KEY) else {
+            return;
+        };
+        let _ = storage.remove_item(PENDING_SAVE_KEY);
+        let Ok(pending) = serde_json::from_str::<PendingSave>(&json) else {
+            return;
+        };
+        let stored_time = pkv
+            .get::<serde_json::Value>(save_key(pending.slot))
+            .ok()
+            .and_then(|stored| stored.get("last_tick_time_since2023")?.as_f64())
+            .unwrap_or(f64::MIN);
+        if pending.save.last_tick_time_since2023 > stored_time {
+            info!("recovering save made while the page was hidden");
+            if let Err(err) = pkv.set(save_key(pending.slot), &pending.save) {
+                error!("could not recover hidden page save: {}", err);
+            }
+        }
+    }
+}
@@ -0,0 +1,306 @@
+use bevy::{prelude::*, reflect::TypeUuid};
+use serde::Deserialize;
+
+use crate::big_number::BigNumber;
+use crate::json_asset::{JsonAssetPlugin, ValidateAsset};
+use crate::new_node::timer_for_currencies;
+use crate::upgrade_shop::UpgradeCost;
+
+/// Tuning file, edits are picked up while the game runs on native.
+pub const BALANCE_PATH: &str = "game.balance.json";
+
+pub struct BalancePlugin;
+
+impl Plugin for BalancePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(JsonAssetPlugin::<Balance>::new(
+            BALANCE_PATH,
+            &["balance.json"],
+        ));
+    }
+}
+
+/// Game tuning, read from [`BALANCE_PATH`].
+///
+/// The resource is a copy of the loaded asset, it keeps these defaults until then.
+/// A file failing [`Balance::validate`] is ignored.
+///
+/// The defaults must match the shipped file, the balancing binary falls back on them.
+#[derive(Resource, Deserialize, TypeUuid, Clone, Debug, PartialEq)]
+#[uuid = "0c5e3c47-5d57-4a43-9a43-8b3f0f6d4e21"]
+#[serde(default)]
+pub struct Balance {
+    pub timer_blocker_mult: f32,
+    pub timer_reset_blocker_fixed: f32,
+    pub timer_gain_mult: f32,
+    pub timer_gain_mult_per_level: f32,
+    pub timer_save_base: f32,
+    pub timer_save_mult_per_level: f32,
+    pub timer_save_add_mult_per_currency: f32,
+    /// Timers of nodes blocked by another node are this many times slower.
+    pub blocked_timer_slowdown: f32,
+    /// Time for a multiplier to charge again.
+    pub timer_multiplier_base: f32,
+    pub timer_multiplier_mult_per_level: f32,
+    pub timer_converter: f32,
+    /// Coins consumed by a converter, multiplied by the growth for each level.
+    pub converter_cost_base: f32,
+    pub converter_cost_growth: f32,
+    /// Wait before a gate can be switched again.
+    pub timer_gate: f32,
+    /// Cycle of a bank, its deposit grows once per cycle.
+    pub timer_bank: f32,
+    /// Part of the coins a bank takes when clicked.
+    pub bank_deposit_share: f32,
+    pub bank_rate_base: f32,
+    pub bank_rate_per_level: f32,
+    /// A deposit stops growing after this many cycles, until withdrawn.
+    pub bank_max_cycles: u32,
+    /// Wait before an overclock can be used again.
+    pub timer_overclock: f32,
+    /// How long an overclock buff lasts, in real time.
+    pub overclock_seconds: f32,
+    pub overclock_global_factor: f32,
+    pub overclock_regional_factor: f32,
+    pub overclock_factor_per_level: f32,
+    /// Overclocks stop gaining levels, and factor, past this.
+    pub overclock_max_level: u32,
+    /// Coins to earn in a run for the first prestige point.
+    pub prestige_base_coins: f32,
+    /// Points grow with coins earned to this power.
+    pub prestige_power: f32,
+    pub upgrade_gain_speed: UpgradeCost,
+    pub upgrade_gain_yield: UpgradeCost,
+    pub upgrade_gain_auto_click: UpgradeCost,
+    pub upgrade_save_speed: UpgradeCost,
+    pub upgrade_save_auto_click: UpgradeCost,
+    pub upgrade_blocker_speed: UpgradeCost,
+    pub upgrade_blocker_auto_click: UpgradeCost,
+    pub upgrade_multiplier_speed: UpgradeCost,
+    pub upgrade_converter_speed: UpgradeCost,
+    pub upgrade_converter_auto_click: UpgradeCost,
+    pub upgrade_gate_speed: UpgradeCost,
+    pub upgrade_bank_speed: UpgradeCost,
+    pub upgrade_overclock_speed: UpgradeCost,
+}
+
+impl Default for Balance {
+    fn default() -> Self {
+        Self {
+            timer_blocker_mult: 0.5f32,
+            timer_reset_blocker_fixed: 0.5f32,
+            timer_gain_mult: 0.3f32,
+            timer_gain_mult_per_level: 2f32,
+            timer_save_base: 5f32,
+            timer_save_mult_per_level: 5f32,
+            timer_save_add_mult_per_currency: 0.5f32,
+            blocked_timer_slowdown: 20f32,
+            timer_multiplier_base: 20f32,
+            timer_multiplier_mult_per_level: 10f32,
+            timer_converter: 10f32,
+            converter_cost_base: 10f32,
+            converter_cost_growth: 1.6f32,
+            timer_gate: 2f32,
+            timer_bank: 15f32,
+            bank_deposit_share: 0.5f32,
+            bank_rate_base: 0.05f32,
+            bank_rate_per_level: 0.01f32,
+            bank_max_cycles: 20,
+            timer_overclock: 60f32,
+            overclock_seconds: 20f32,
+            overclock_global_factor: 1.5f32,
+            overclock_regional_factor: 2.5f32,
+            overclock_factor_per_level: 0.1f32,
+            overclock_max_level: 10,
+            prestige_base_coins: 100f32,
+            prestige_power: 0.5f32,
+            upgrade_gain_speed: UpgradeCost::new(10f64, 1.5f64),
+            upgrade_gain_yield: UpgradeCost::new(25f64, 2f64),
+            upgrade_gain_auto_click: UpgradeCost::new(50f64, 3f64),
+            upgrade_save_speed: UpgradeCost::new(15f64, 1.6f64),
+            upgrade_save_auto_click: UpgradeCost::new(50f64, 3f64),
+            upgrade_blocker_speed: UpgradeCost::new(5f64, 1.4f64),
+            upgrade_blocker_auto_click: UpgradeCost::new(20f64, 2.5f64),
+            upgrade_multiplier_speed: UpgradeCost::new(12f64, 1.5f64),
+            upgrade_converter_speed: UpgradeCost::new(15f64, 1.6f64),
+            upgrade_converter_auto_click: UpgradeCost::new(50f64, 3f64),
+            upgrade_gate_speed: UpgradeCost::new(5f64, 1.4f64),
+            upgrade_bank_speed: UpgradeCost::new(30f64, 2f64),
+            upgrade_overclock_speed: UpgradeCost::new(20f64, 1.6f64),
+        }
+    }
+}
+
+impl ValidateAsset for Balance {
+    /// All values are used as durations or factors of durations, they must be positive.
+    /// Upgrade costs must pass [`UpgradeCost::is_valid`].
+    fn validate(&self) -> Result<(), String> {
+        let values = [
+            ("timer_blocker_mult", self.timer_blocker_mult),
+            ("timer_reset_blocker_fixed", self.timer_reset_blocker_fixed),
+            ("timer_gain_mult", self.timer_gain_mult),
+            ("timer_gain_mult_per_level", self.timer_gain_mult_per_level),
+            ("timer_save_base", self.timer_save_base),
+            ("timer_save_mult_per_level", self.timer_save_mult_per_level),
+            (
+                "timer_save_add_mult_per_currency",
+                self.timer_save_add_mult_per_currency,
+            ),
+            ("blocked_timer_slowdown", self.blocked_timer_slowdown),
+            ("timer_multiplier_base", self.timer_multiplier_base),
+            (
+                "timer_multiplier_mult_per_level",
+                self.timer_multiplier_mult_per_level,
+            ),
+            ("timer_converter", self.timer_converter),
+            ("converter_cost_base", self.converter_cost_base),
+            ("converter_cost_growth", self.converter_cost_growth),
+            ("timer_gate", self.timer_gate),
+            ("timer_bank", self.timer_bank),
+            ("bank_deposit_share", self.bank_deposit_share),
+            ("bank_rate_base", self.bank_rate_base),
+            ("bank_rate_per_level", self.bank_rate_per_level),
+            ("timer_overclock", self.timer_overclock),
+            ("overclock_seconds", self.overclock_seconds),
+            ("overclock_global_factor", self.overclock_global_factor),
+            ("overclock_regional_factor", self.overclock_regional_factor),
+            (
+                "overclock_factor_per_level",
+                self.overclock_factor_per_level,
+            ),
+            ("prestige_base_coins", self.prestige_base_coins),
+            ("prestige_power", self.prestige_power),
+        ];
+        if let Some((name, value)) = values
+            .iter()
+            .find(|(_, value)| !value.is_finite() || *value <= 0f32)
+        {
+            return Err(format!("{} should be positive, got {}", name, value));
+        }
+        let costs = [
+            ("upgrade_gain_speed", self.upgrade_gain_speed),
+            ("upgrade_gain_yield", self.upgrade_gain_yield),
+            ("upgrade_gain_auto_click", self.upgrade_gain_auto_click),
+            ("upgrade_save_speed", self.upgrade_save_speed),
+            ("upgrade_save_auto_click", self.upgrade_save_auto_click),
+            ("upgrade_blocker_speed", self.upgrade_blocker_speed),
+            (
+                "upgrade_blocker_auto_click",
+                self.upgrade_blocker_auto_click,
+            ),
+            ("upgrade_multiplier_speed", self.upgrade_multiplier_speed),
+            ("upgrade_converter_speed", self.upgrade_converter_speed),
+            (
+                "upgrade_converter_auto_click",
+                self.upgrade_converter_auto_click,
+            ),
+            ("upgrade_gate_speed", self.upgrade_gate_speed),
+            ("upgrade_bank_speed", self.upgrade_bank_speed),
+            ("upgrade_overclock_speed", self.upgrade_overclock_speed),
+        ];
+        if let Some((name, cost)) = costs.iter().find(|(_, cost)| !cost.is_valid()) {
+            return Err(format!(
+                "{} should have a positive base and a growth of at least 1, got {:?}",
+                name, cost
+            ));
+        }
+        // Banks would take more coins than owned.
+        if self.bank_deposit_share > 1f32 {
+            return Err(format!(
+                "bank_deposit_share should be at most 1, got {}",
+                self.bank_deposit_share
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Balance {
+    /// Duration of a blocker spawned when owning `currencies`.
+    pub fn blocker_duration(&self, currencies: BigNumber) -> f32 {
+        timer_for_currencies(currencies, self.timer_blocker_mult)
+    }
+
+    /// Duration of a gain at `level`, owning `amount` of its resource.
+    pub fn gain_duration(&self, amount: BigNumber, level: u32) -> f32 {
+        timer_for_currencies(amount, self.timer_gain_mult)
+            + self.timer_gain_mult_per_level * level as f32
+    }
+
+    /// Duration of a save at `level`, owning `coins`.
+    pub fn save_duration(&self, level: u32, coins: BigNumber) -> f32 {
+        self.timer_save_base
+            + level as f32 * self.timer_save_mult_per_level
+            + timer_for_currencies(coins, self.timer_save_add_mult_per_currency)
+    }
+
+    /// Interest per cycle of a deposit in a bank at `level`.
+    pub fn bank_rate(&self, level: u32) -> f64 {
+        (self.bank_rate_base + self.bank_rate_per_level * level as f32) as f64
+    }
+
+    /// Duration for a multiplier at `level` to charge.
+    pub fn multiplier_duration(&self, level: u32) -> f32 {
+        self.timer_multiplier_base + self.timer_multiplier_mult_per_level * level as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shipped_balance_is_valid() {
+        assert_eq!(Balance::default().validate(), Ok(()));
+        let file: Balance =
+            serde_json::from_str(include_str!("../assets/game.balance.json")).unwrap();
+        assert_eq!(file.validate(), Ok(()));
+    }
+
+    #[test]
+    fn shipped_balance_matches_default() {
+        let file: Balance =
+            serde_json::from_str(include_str!("../assets/game.balance.json")).unwrap();
+        assert_eq!(file, Balance::default());
+    }
+
+    #[test]
+    fn rejects_values_that_would_panic() {
+        for balance in [
+            Balance {
+                blocked_timer_slowdown: 0f32,
+                ..default()
+            },
+            Balance {
+                timer_gate: -1f32,
+                ..default()
+            },
+            Balance {
+                overclock_factor_per_level: f32::NAN,
+                ..default()
+            },
+            Balance {
+                timer_bank: f32::INFINITY,
+                ..default()
+            },
+            Balance {
+                bank_deposit_share: 1.5f32,
+                ..default()
+            },
+            Balance {
+                prestige_power: 0f32,
+                ..default()
+            },
+            Balance {
+                upgrade_gain_speed: UpgradeCost::new(0f64, 1.5f64),
+                ..default()
+            },
+            Balance {
+                upgrade_bank_speed: UpgradeCost::new(30f64, 0.5f64),
+                ..default()
+            },
+        ] {
+            assert!(balance.validate().is_err(), "{:?}", balance);
+        }
+    }
+}
@@ -0,0 +1,250 @@
+use bevy::prelude::*;
+
+use crate::automation::{ActivateNode, NodeActivation};
+use crate::balance::Balance;
+use crate::big_number::BigNumber;
+use crate::idle_gains::{Ledger, ResourceKind};
+use crate::number_format::{format_number, Notation};
+use crate::persisted_game::{NodeType, SavedNode};
+use crate::progress::{NodeTimer, Progress};
+use crate::upgrade_shop::NodeUpgrades;
+use crate::InheritedBlockStatus;
+
+pub struct BankPlugin;
+
+impl Plugin for BankPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(node_bank_react.after(NodeActivation))
+            .add_system(accrue_bank_interest);
+    }
+}
+
+/// Takes coins on a click when ready, they grow by `rate` each time its timer finishes,
+/// up to `bank_max_cycles` times.
+///
+/// The next click withdraws everything, at any time.
+#[derive(Component)]
+pub struct NodeBank {
+    pub level: u32,
+    pub deposit: BigNumber,
+    /// Interest per cycle, fixed when depositing.
+    pub rate: f64,
+    /// Cycles the deposit grew.
+    pub cycles: u32,
+}
+
+impl NodeBank {
+    pub fn holds_deposit(&self) -> bool {
+        !self.deposit.is_zero()
+    }
+
+    /// Whether the deposit still grows when the timer finishes.
+    pub fn is_growing(&self, balance: &Balance) -> bool {
+        self.holds_deposit() && self.cycles < balance.bank_max_cycles
+    }
+
+    /// Compounds up to `cycles` at once, without going past `bank_max_cycles`.
+    pub fn accrue(&mut self, cycles: u32, balance: &Balance) {
+        let cycles = cycles.min(balance.bank_max_cycles.saturating_sub(self.cycles));
+        if cycles == 0 {
+            return;
+        }
+        let growth = BigNumber::from(1f64 + self.rate).powf(cycles as f64);
+        self.deposit = self.deposit * growth;
+        self.cycles += cycles;
+    }
+
+    /// Withdraws everything at any time, or deposits a share of the coins when ready.
+    ///
+    /// Returns false when nothing happened.
+    pub fn click(
+        &mut self,
+        timer: &mut impl NodeTimer,
+        upgrades: &NodeUpgrades,
+        ledger: &mut Ledger,
+        balance: &Balance,
+    ) -> bool {
+        if self.holds_deposit() {
+            ledger.add(ResourceKind::Coins, self.deposit);
+            self.deposit = BigNumber::ZERO;
+            self.cycles = 0;
+            self.level += 1;
+        } else if timer.is_finished() {
+            let deposit =
+                (ledger.get(ResourceKind::Coins) * balance.bank_deposit_share as f64).floor();
+            if deposit.is_zero() {
+                return false;
+            }
+            ledger.add(ResourceKind::Coins, -deposit);
+            self.deposit = deposit;
+            self.rate = balance.bank_rate(self.level);
+            self.cycles = 0;
+        } else {
+            return false;
+        }
+        // Cycle of the interest, or wait before the next deposit.
+        timer.restart(balance.timer_bank * upgrades.duration_factor());
+        true
+    }
+
+    pub fn label(&self, notation: Notation) -> String {
+        format!(
+            "{} +{:.0}%",
+            format_number(self.deposit, notation),
+            self.rate * 100f64
+        )
+    }
+}
+
+/// Advances the timer of a saved bank by `seconds`, compounding all the cycles finished
+/// at once, so long offline times cost the same as short ones.
+///
+/// Like `accrue_bank_interest`, the timer restarts for each cycle and stays finished after the last.
+pub fn advance_saved_bank(node: &mut SavedNode, seconds: f32, balance: &Balance) {
+    let NodeType::Bank {
+        level,
+        deposit,
+        rate,
+        cycles,
+    } = node.node_type
+    else {
+        return;
+    };
+    let mut bank = NodeBank {
+        level,
+        deposit,
+        rate,
+        cycles,
+    };
+    let duration = node.timer_seconds_duration;
+    if !bank.is_growing(balance) || seconds < node.timer_seconds_left {
+        node.timer_seconds_left = (node.timer_seconds_left - seconds).max(0f32);
+        return;
+    }
+    let past_finish = seconds - node.timer_seconds_left;
+    let finished_cycles = if duration > 0f32 {
+        // Saturates for huge times, `accrue` caps it anyway.
+        1 + (past_finish / duration) as u32
+    } else {
+        u32::MAX
+    };
+    bank.accrue(finished_cycles, balance);
+    node.timer_seconds_left = if bank.is_growing(balance) && duration > 0f32 {
+        duration - past_finish % duration
+    } else {
+        0f32
+    };
+    node.node_type = NodeType::Bank {
+        level: bank.level,
+        deposit: bank.deposit,
+        rate: bank.rate,
+        cycles: bank.cycles,
+    };
+}
+
+fn node_bank_react(
+    mut events: EventReader<ActivateNode>,
+    mut q_banks: Query<(
+        &mut Progress,
+        &mut NodeBank,
+        &NodeUpgrades,
+        &InheritedBlockStatus,
+    )>,
+    mut ledger: ResMut<Ledger>,
+    balance: Res<Balance>,
+) {
+    for event in events.iter() {
+        let Ok((mut p, mut bank, upgrades, status)) = q_banks.get_mut(event.0) else {
+            continue;
+        };
+        if !status.is_blocked {
+            bank.click(&mut *p, upgrades, &mut ledger, &balance);
+        }
+    }
+}
+
+/// The timer of a bank done growing stays finished, until the withdrawal.
+fn accrue_bank_interest(mut q_banks: Query<(&mut Progress, &mut NodeBank)>, balance: Res<Balance>) {
+    for (mut p, mut bank) in q_banks.iter_mut() {
+        if bank.is_growing(&balance) && p.timer.finished() {
+            bank.accrue(1, &balance);
+            if bank.is_growing(&balance) {
+                p.timer.reset();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::offline_progress::fast_forward;
+    use crate::persisted_game::test_saves::{node, save_with};
+
+    fn saved_bank(deposit: f64, duration: f32) -> SavedNode {
+        let bank = NodeType::Bank {
+            level: 0,
+            deposit: BigNumber::from(deposit),
+            rate: 0.1,
+            cycles: 0,
+        };
+        node(bank, duration, duration)
+    }
+
+    fn deposit_and_cycles(node: &SavedNode) -> (f64, u32) {
+        match node.node_type {
+            NodeType::Bank {
+                deposit, cycles, ..
+            } => (deposit.to_f64(), cycles),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn accrue_stops_at_max_cycles() {
+        let balance = Balance::default();
+        let mut bank = NodeBank {
+            level: 0,
+            deposit: BigNumber::from(100f64),
+            rate: 0.1,
+            cycles: balance.bank_max_cycles - 1,
+        };
+        bank.accrue(5, &balance);
+        assert_eq!(bank.cycles, balance.bank_max_cycles);
+        assert!((bank.deposit.to_f64() - 110f64).abs() < 1e-9);
+        assert!(!bank.is_growing(&balance));
+        bank.accrue(1, &balance);
+        assert!((bank.deposit.to_f64() - 110f64).abs() < 1e-9);
+    }
+
+    #[test]
+    fn advancing_at_once_matches_cycle_by_cycle() {
+        let balance = Balance::default();
+        let mut at_once = saved_bank(100f64, 2f32);
+        advance_saved_bank(&mut at_once, 7f32, &balance);
+        let mut by_steps = saved_bank(100f64, 2f32);
+        for _ in 0..14 {
+            advance_saved_bank(&mut by_steps, 0.5, &balance);
+        }
+        let (deposit, cycles) = deposit_and_cycles(&at_once);
+        assert_eq!(cycles, 3);
+        assert_eq!(deposit_and_cycles(&by_steps).1, 3);
+        assert!((deposit - 133.1).abs() < 1e-6);
+        assert!((deposit - deposit_and_cycles(&by_steps).0).abs() < 1e-6);
+        assert!((at_once.timer_seconds_left - 1f32).abs() < 1e-5);
+        assert!((by_steps.timer_seconds_left - 1f32).abs() < 1e-5);
+    }
+
+    #[test]
+    fn a_week_offline_is_capped() {
+        let balance = Balance::default();
+        let mut save = save_with(vec![saved_bank(100f64, balance.timer_bank)]);
+        let now = save.last_tick_time_since2023 + 7f64 * 24f64 * 3600f64;
+        fast_forward(&mut save, now, &balance);
+        let (deposit, cycles) = deposit_and_cycles(&save.nodes[0]);
+        assert_eq!(cycles, balance.bank_max_cycles);
+        let expected = 100f64 * 1.1f64.powi(balance.bank_max_cycles as i32);
+        assert!((deposit - expected).abs() < 1e-6 * expected);
+        assert_eq!(save.nodes[0].timer_seconds_left, 0f32);
+    }
+}
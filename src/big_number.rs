@@ -0,0 +1,436 @@
+use std::cmp::Ordering;
+use std::ops::{Add, AddAssign, Div, Mul, Neg, Sub, SubAssign};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Beyond this exponent difference, the smaller number doesn't change an addition.
+const MAX_SIGNIFICANT_EXPONENT_DIFF: i64 = 17;
+
+/// A number `mantissa * 10^exponent`, to count currencies way beyond integer limits.
+///
+/// Always normalized: `1 <= |mantissa| < 10`, or both are 0.
+#[derive(Reflect, FromReflect, Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(from = "BigNumberRepr")]
+pub struct BigNumber {
+    mantissa: f64,
+    exponent: i64,
+}
+
+/// Also accepts plain numbers, as stored before big numbers.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum BigNumberRepr {
+    Number(f64),
+    Parts { mantissa: f64, exponent: i64 },
+}
+
+impl From<BigNumberRepr> for BigNumber {
+    fn from(repr: BigNumberRepr) -> Self {
+        match repr {
+            BigNumberRepr::Number(value) => BigNumber::from(value),
+            BigNumberRepr::Parts { mantissa, exponent } => BigNumber::new(mantissa, exponent),
+        }
+    }
+}
+
+impl BigNumber {
+    pub const ZERO: BigNumber = BigNumber {
+        mantissa: 0f64,
+        exponent: 0,
+    };
+    pub const ONE: BigNumber = BigNumber {
+        mantissa: 1f64,
+        exponent: 0,
+    };
+    /// Overflows saturate to it, its exponent leaves room to add exponents without overflowing.
+    pub const MAX: BigNumber = BigNumber {
+        mantissa: 9.999_999_999_999_998,
+        exponent: i64::MAX / 4,
+    };
+
+    /// Normalizes `mantissa * 10^exponent`.
+    ///
+    /// An infinite mantissa, like an overflowing product, saturates to [`BigNumber::MAX`]
+    /// keeping its sign, so huge amounts don't drop to zero. NaN gives zero.
+    pub fn new(mantissa: f64, exponent: i64) -> Self {
+        if mantissa == 0f64 || mantissa.is_nan() {
+            return BigNumber::ZERO;
+        }
+        if mantissa.is_infinite() {
+            return BigNumber::MAX.with_sign_of(mantissa);
+        }
+        let shift = mantissa.abs().log10().floor() as i64;
+        let mut number = BigNumber {
+            mantissa: mantissa / 10f64.powi(shift as i32),
+            exponent: exponent + shift,
+        };
+        // log10 may be off by one because of rounding.
+        if number.mantissa.abs() >= 10f64 {
+            number.mantissa /= 10f64;
+            number.exponent += 1;
+        } else if number.mantissa.abs() < 1f64 {
+            number.mantissa *= 10f64;
+            number.exponent -= 1;
+        }
+        if number.exponent > BigNumber::MAX.exponent {
+            return BigNumber::MAX.with_sign_of(mantissa);
+        }
+        if number.exponent < -BigNumber::MAX.exponent {
+            return BigNumber::ZERO;
+        }
+        number
+    }
+
+    fn with_sign_of(self, value: f64) -> BigNumber {
+        BigNumber {
+            mantissa: self.mantissa.copysign(value),
+            exponent: self.exponent,
+        }
+    }
+
+    pub fn mantissa(self) -> f64 {
+        self.mantissa
+    }
+
+    pub fn exponent(self) -> i64 {
+        self.exponent
+    }
+
+    pub fn is_zero(self) -> bool {
+        self.mantissa == 0f64
+    }
+
+    /// Saturates to `f64::MAX` (or `f64::MIN`) when too big.
+    pub fn to_f64(self) -> f64 {
+        if self.exponent > f64::MAX_10_EXP as i64 {
+            return f64::MAX.copysign(self.mantissa);
+        }
+        if self.exponent < f64::MIN_10_EXP as i64 {
+            return 0f64;
+        }
+        (self.mantissa * 10f64.powi(self.exponent as i32)).clamp(f64::MIN, f64::MAX)
+    }
+
+    /// Saturates to `f32::MAX` (or `f32::MIN`) when too big.
+    pub fn to_f32(self) -> f32 {
+        self.to_f64().clamp(f32::MIN as f64, f32::MAX as f64) as f32
+    }
+
+    /// `self` to the power of `power`, 0 for non-positive numbers.
+    pub fn powf(self, power: f64) -> BigNumber {
+        if self.mantissa <= 0f64 {
+            return BigNumber::ZERO;
+        }
+        let log = (self.mantissa.log10() + self.exponent as f64) * power;
+        if log > BigNumber::MAX.exponent as f64 {
+            return BigNumber::MAX;
+        }
+        let exponent = log.floor();
+        BigNumber::new(10f64.powf(log - exponent), exponent as i64)
+    }
+
+    pub fn floor(self) -> BigNumber {
+        if self.exponent >= MAX_SIGNIFICANT_EXPONENT_DIFF {
+            self
+        } else {
+            BigNumber::from(self.to_f64().floor())
+        }
+    }
+}
+
+impl From<f64> for BigNumber {
+    fn from(value: f64) -> Self {
+        BigNumber::new(value, 0)
+    }
+}
+
+impl From<i32> for BigNumber {
+    fn from(value: i32) -> Self {
+        BigNumber::new(value as f64, 0)
+    }
+}
+
+impl From<u32> for BigNumber {
+    fn from(value: u32) -> Self {
+        BigNumber::new(value as f64, 0)
+    }
+}
+
+impl Neg for BigNumber {
+    type Output = BigNumber;
+
+    fn neg(self) -> BigNumber {
+        BigNumber {
+            mantissa: -self.mantissa,
+            exponent: self.exponent,
+        }
+    }
+}
+
+impl Add for BigNumber {
+    type Output = BigNumber;
+
+    fn add(self, other: BigNumber) -> BigNumber {
+        if self.is_zero() {
+            return other;
+        }
+        if other.is_zero() {
+            return self;
+        }
+        let (big, small) = if self.exponent >= other.exponent {
+            (self, other)
+        } else {
+            (other, self)
+        };
+        let diff = big.exponent - small.exponent;
+        if diff > MAX_SIGNIFICANT_EXPONENT_DIFF {
+            return big;
+        }
+        BigNumber::new(
+            big.mantissa + small.mantissa / 10f64.powi(diff as i32),
+            big.exponent,
+        )
+    }
+}
+
+impl Sub for BigNumber {
+    type Output = BigNumber;
+
+    fn sub(self, other: BigNumber) -> BigNumber {
+        self + -other
+    }
+}
+
+impl Mul for BigNumber {
+    type Output = BigNumber;
+
+    fn mul(self, other: BigNumber) -> BigNumber {
+        BigNumber::new(
+            self.mantissa * other.mantissa,
+            self.exponent + other.exponent,
+        )
+    }
+}
+
+impl Mul<f64> for BigNumber {
+    type Output = BigNumber;
+
+    fn mul(self, other: f64) -> BigNumber {
+        BigNumber::new(self.mantissa * other, self.exponent)
+    }
+}
+
+impl Div for BigNumber {
+    type Output = BigNumber;
+
+    /// Dividing by zero gives zero.
+    fn div(self, other: BigNumber) -> BigNumber {
+        if other.is_zero() {
+            return BigNumber::ZERO;
+        }
+        BigNumber::new(
+            self.mantissa / other.mantissa,
+            self.exponent - other.exponent,
+        )
+    }
+}
+
+impl AddAssign for BigNumber {
+    fn add_assign(&mut self, other: BigNumber) {
+        *self = *self + other;
+    }
+}
+
+impl SubAssign for BigNumber {
+    fn sub_assign(&mut self, other: BigNumber) {
+        *self = *self - other;
+    }
+}
+
+impl PartialOrd for BigNumber {
+    fn partial_cmp(&self, other: &BigNumber) -> Option<Ordering> {
+        let sign = |n: &BigNumber| n.mantissa.partial_cmp(&0f64);
+        match sign(self)?.cmp(&sign(other)?) {
+            Ordering::Equal => {}
+            ordering => return Some(ordering),
+        }
+        if self.is_zero() {
+            return Some(Ordering::Equal);
+        }
+        let ordering = self
+            .exponent
+            .cmp(&other.exponent)
+            .then(self.mantissa.abs().partial_cmp(&other.mantissa.abs())?);
+        // Bigger magnitude means smaller for negative numbers.
+        Some(if self.mantissa < 0f64 {
+            ordering.reverse()
+        } else {
+            ordering
+        })
+    }
+}
+
+impl std::fmt::Display for BigNumber {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.exponent < 6 {
+            write!(f, "{:.0}", self.to_f64())
+        } else {
+            write!(f, "{:.2}e{}", self.mantissa, self.exponent)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(number: BigNumber, mantissa: f64, exponent: i64) {
+        assert_eq!(number.exponent(), exponent, "{:?}", number);
+        assert!(
+            (number.mantissa() - mantissa).abs() < 1e-9,
+            "{:?} should have a mantissa of {}",
+            number,
+            mantissa
+        );
+    }
+
+    #[test]
+    fn normalization() {
+        assert_close(BigNumber::from(123f64), 1.23, 2);
+        assert_close(BigNumber::from(0.05), 5f64, -2);
+        assert_close(BigNumber::new(-4500f64, 1), -4.5, 4);
+        assert_close(BigNumber::new(1000f64, 0), 1f64, 3);
+        assert_eq!(BigNumber::new(0f64, 5), BigNumber::ZERO);
+        // Whatever log10 rounding does to powers of 10.
+        for exponent in -300..300 {
+            for value in [10f64.powi(exponent), 9.99 * 10f64.powi(exponent)] {
+                let mantissa = BigNumber::from(value).mantissa();
+                assert!(
+                    (1f64..10f64).contains(&mantissa),
+                    "{} of {}",
+                    mantissa,
+                    value
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn nan_is_zero() {
+        assert_eq!(BigNumber::from(f64::NAN), BigNumber::ZERO);
+        assert_eq!(BigNumber::new(f64::NAN, 3), BigNumber::ZERO);
+    }
+
+    #[test]
+    fn overflow_saturates() {
+        assert_eq!(BigNumber::from(f64::INFINITY), BigNumber::MAX);
+        assert_eq!(BigNumber::new(f64::NEG_INFINITY, 3), -BigNumber::MAX);
+        // The mantissas product overflows f64.
+        assert_eq!(BigNumber::from(f64::MAX) * f64::MAX, BigNumber::MAX);
+        assert_eq!(BigNumber::from(-f64::MAX) * f64::MAX, -BigNumber::MAX);
+        assert_eq!(BigNumber::MAX * BigNumber::MAX, BigNumber::MAX);
+        assert_eq!(BigNumber::from(10f64).powf(1e30), BigNumber::MAX);
+        assert!(BigNumber::MAX > BigNumber::new(1f64, 1000));
+        assert_eq!(
+            BigNumber::ONE / BigNumber::MAX / BigNumber::MAX,
+            BigNumber::ZERO
+        );
+    }
+
+    #[test]
+    fn add() {
+        assert_close(BigNumber::new(1f64, 3) + BigNumber::new(5f64, 2), 1.5, 3);
+        assert_close(BigNumber::from(5f64) + BigNumber::from(5f64), 1f64, 1);
+        assert_close(BigNumber::from(5f64) - BigNumber::from(7f64), -2f64, 0);
+        assert_eq!(
+            BigNumber::from(5f64) - BigNumber::from(5f64),
+            BigNumber::ZERO
+        );
+        assert_eq!(BigNumber::ZERO + BigNumber::ONE, BigNumber::ONE);
+    }
+
+    #[test]
+    fn add_ignores_insignificant_numbers() {
+        let big = BigNumber::new(1f64, MAX_SIGNIFICANT_EXPONENT_DIFF + 10);
+        assert_eq!(big + BigNumber::ONE, big);
+        assert_eq!(BigNumber::ONE + big, big);
+        assert_eq!(big - BigNumber::ONE, big);
+        // Right at the limit, the smaller number still counts.
+        let at_limit = BigNumber::new(1f64, MAX_SIGNIFICANT_EXPONENT_DIFF);
+        assert_close(at_limit + at_limit, 2f64, MAX_SIGNIFICANT_EXPONENT_DIFF);
+    }
+
+    #[test]
+    fn ordering() {
+        let minus_hundred = BigNumber::from(-100f64);
+        let minus_five = BigNumber::from(-5f64);
+        let three = BigNumber::from(3f64);
+        let thousand = BigNumber::from(1000f64);
+        assert!(minus_hundred < minus_five);
+        assert!(minus_five < BigNumber::ZERO);
+        assert!(BigNumber::ZERO < three);
+        assert!(three < thousand);
+        assert!(BigNumber::new(9.9, 19) < BigNumber::new(1f64, 20));
+        assert!(minus_five > minus_hundred);
+        assert_eq!(
+            BigNumber::ZERO.partial_cmp(&-BigNumber::ZERO),
+            Some(Ordering::Equal)
+        );
+        assert_eq!(three.partial_cmp(&three), Some(Ordering::Equal));
+    }
+
+    #[test]
+    fn mul_and_div() {
+        assert_close(
+            BigNumber::new(5f64, 10) * BigNumber::new(4f64, 10),
+            2f64,
+            21,
+        );
+        assert_close(BigNumber::from(6f64) / BigNumber::from(3f64), 2f64, 0);
+        assert_close(BigNumber::ONE / BigNumber::new(4f64, 100), 2.5, -101);
+    }
+
+    #[test]
+    fn division_by_zero_is_zero() {
+        assert_eq!(BigNumber::from(6f64) / BigNumber::ZERO, BigNumber::ZERO);
+        assert_eq!(BigNumber::ZERO / BigNumber::ZERO, BigNumber::ZERO);
+    }
+
+    #[test]
+    fn powf() {
+        assert_close(BigNumber::from(2f64).powf(10f64), 1.024, 3);
+        assert_close(BigNumber::new(1f64, 100).powf(3f64), 1f64, 300);
+        assert_close(BigNumber::from(4f64).powf(0.5), 2f64, 0);
+        assert_eq!(BigNumber::from(7f64).powf(0f64), BigNumber::ONE);
+        assert_eq!(BigNumber::from(-2f64).powf(2f64), BigNumber::ZERO);
+        assert_eq!(BigNumber::ZERO.powf(2f64), BigNumber::ZERO);
+    }
+
+    #[test]
+    fn serde_roundtrip() {
+        let number = BigNumber::new(1.5, 300);
+        let json = serde_json::to_string(&number).unwrap();
+        assert_eq!(serde_json::from_str::<BigNumber>(&json).unwrap(), number);
+    }
+
+    #[test]
+    fn serde_reads_legacy_numbers() {
+        assert_eq!(
+            serde_json::from_str::<BigNumber>("42").unwrap(),
+            BigNumber::from(42f64)
+        );
+        assert_eq!(
+            serde_json::from_str::<BigNumber>("-7").unwrap(),
+            BigNumber::from(-7f64)
+        );
+        assert_eq!(
+            serde_json::from_str::<BigNumber>("0.5").unwrap(),
+            BigNumber::from(0.5)
+        );
+        assert_eq!(
+            serde_json::from_str::<BigNumber>(r#"{"mantissa":1.5,"exponent":3}"#).unwrap(),
+            BigNumber::from(1500f64)
+        );
+    }
+}
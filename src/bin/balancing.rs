@@ -0,0 +1,200 @@
+//! Plays the game headlessly with bots, to compare balance changes.
+//!
+//! Bots click a [`Simulation`], which runs the same rules as the game,
+//! auto-clickers and node rules included.
+//!
+//! `cargo run --release --bin balancing -- [strategy|all] [seed] [seconds]`
+//!
+//! Reads the tuning files from `assets/`, and prints a csv to stdout:
+//! a `sample` row every [`SAMPLE_SECONDS`], and a row when coins reach each power of 10.
+
+use std::fs;
+use std::path::Path;
+
+use serde::de::DeserializeOwned;
+use sidle_ffect::balance::{Balance, BALANCE_PATH};
+use sidle_ffect::big_number::BigNumber;
+use sidle_ffect::idle_gains::ResourceKind;
+use sidle_ffect::persisted_game::NodeType;
+use sidle_ffect::simulation::Simulation;
+use sidle_ffect::spawn_table::{SpawnTable, SPAWN_TABLE_PATH};
+use sidle_ffect::upgrade_shop::Upgrade;
+
+/// Fixed timestep of the simulation, bots decide once per step.
+const STEP_SECONDS: f32 = 0.1f32;
+const SAMPLE_SECONDS: f64 = 60f64;
+const DEFAULT_SEED: u64 = 0;
+const DEFAULT_SECONDS: f64 = 3600f64;
+
+#[derive(Clone, Copy)]
+enum Strategy {
+    /// Clicks everything auto-clickers would: ready gains and saves, and unblocks blockers.
+    ClickEverything,
+    /// Clicks ready gains and saves, blockers stay blocked.
+    NeverUnblock,
+    /// Clicks saves first, gains only when no save is ready.
+    SaveHoarder,
+    /// Buys auto-clickers for gains, saves and blockers whenever affordable,
+    /// clicks the nodes without one like [`Strategy::ClickEverything`].
+    AutoClickers,
+}
+
+impl Strategy {
+    const ALL: [Strategy; 4] = [
+        Strategy::ClickEverything,
+        Strategy::NeverUnblock,
+        Strategy::SaveHoarder,
+        Strategy::AutoClickers,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Strategy::ClickEverything => "click-everything",
+            Strategy::NeverUnblock => "never-unblock",
+            Strategy::SaveHoarder => "save-hoarder",
+            Strategy::AutoClickers => "auto-clickers",
+        }
+    }
+
+    /// Upgrades to buy this step.
+    fn buy(self, simulation: &mut Simulation) {
+        if let Strategy::AutoClickers = self {
+            for index in 0..simulation.nodes().len() {
+                if matches!(
+                    simulation.nodes()[index].node_type,
+                    NodeType::Gain { .. } | NodeType::Save { .. } | NodeType::Blocker { .. }
+                ) {
+                    simulation.buy_upgrade(index, Upgrade::AutoClick);
+                }
+            }
+        }
+    }
+
+    /// Nodes to click this step, by index.
+    fn clicks(self, simulation: &Simulation) -> Vec<usize> {
+        let nodes = simulation.nodes();
+        let ready: Vec<(usize, &NodeType)> = simulation
+            .clickable_nodes()
+            .into_iter()
+            .map(|index| (index, &nodes[index].node_type))
+            .collect();
+        let is_save = |node_type: &NodeType| matches!(node_type, NodeType::Save { .. });
+        let any_save_ready = ready.iter().any(|(_, node_type)| is_save(node_type));
+        ready
+            .into_iter()
+            .filter(|(index, _)| {
+                !matches!(self, Strategy::AutoClickers) || nodes[*index].upgrades.auto_click == 0
+            })
+            .filter(|(_, node_type)| match (self, node_type) {
+                (
+                    Strategy::ClickEverything | Strategy::AutoClickers,
+                    NodeType::Blocker { is_blocked },
+                ) => *is_blocked,
+                (_, NodeType::Blocker { .. }) => false,
+                // Gates are left as they spawned.
+                (_, NodeType::Gate { .. }) => false,
+                // When to withdraw would be a strategy of its own.
+                (_, NodeType::Bank { .. }) => false,
+                (Strategy::SaveHoarder, NodeType::Gain { .. }) => !any_save_ready,
+                _ => true,
+            })
+            .map(|(index, _)| index)
+            .collect()
+    }
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let strategies: Vec<Strategy> = match args.first().map(String::as_str) {
+        None | Some("all") => Strategy::ALL.to_vec(),
+        Some(name) => {
+            let Some(strategy) = Strategy::ALL.into_iter().find(|s| s.name() == name) else {
+                eprintln!(
+                    "unknown strategy {}, expected all or one of: {}",
+                    name,
+                    Strategy::ALL.map(Strategy::name).join(", ")
+                );
+                std::process::exit(1);
+            };
+            vec![strategy]
+        }
+    };
+    let seed = args.get(1).map_or(DEFAULT_SEED, |s| {
+        s.parse().expect("seed should be a number")
+    });
+    let seconds = args.get(2).map_or(DEFAULT_SECONDS, |s| {
+        s.parse().expect("duration should be a number of seconds")
+    });
+    let balance: Balance = read_asset(BALANCE_PATH);
+    let spawn_table: SpawnTable = read_asset(SPAWN_TABLE_PATH);
+
+    println!(
+        "strategy,seed,seconds,event,coins,gems,energy,nodes,gains,saves,blockers,multipliers,converters,gates,banks,overclocks"
+    );
+    for strategy in strategies {
+        let mut simulation = Simulation::new(seed, balance.clone(), spawn_table.clone());
+        let mut next_sample = 0f64;
+        let mut next_milestone = 1i64;
+        while simulation.elapsed_seconds() < seconds {
+            strategy.buy(&mut simulation);
+            for index in strategy.clicks(&simulation) {
+                simulation.click(index);
+            }
+            simulation.step(STEP_SECONDS);
+            let coins = simulation.ledger().get(ResourceKind::Coins);
+            while coins >= BigNumber::new(1f64, next_milestone) {
+                print_row(
+                    strategy,
+                    seed,
+                    &simulation,
+                    &format!("coins_1e{}", next_milestone),
+                );
+                next_milestone += 1;
+            }
+            if simulation.elapsed_seconds() >= next_sample {
+                print_row(strategy, seed, &simulation, "sample");
+                next_sample += SAMPLE_SECONDS;
+            }
+        }
+    }
+}
+
+/// Reads a tuning file like the game does, or its defaults when missing.
+fn read_asset<T: DeserializeOwned + Default>(path: &str) -> T {
+    let path = Path::new("assets").join(path);
+    match fs::read(&path) {
+        Ok(bytes) => serde_json::from_slice(&bytes)
+            .unwrap_or_else(|e| panic!("{} should be valid: {}", path.display(), e)),
+        Err(e) => {
+            eprintln!("using defaults, could not read {}: {}", path.display(), e);
+            T::default()
+        }
+    }
+}
+
+fn print_row(strategy: Strategy, seed: u64, simulation: &Simulation, event: &str) {
+    let nodes = simulation.nodes();
+    let count = |is_kind: fn(&NodeType) -> bool| {
+        nodes.iter().filter(|node| is_kind(&node.node_type)).count()
+    };
+    let ledger = simulation.ledger();
+    println!(
+        "{},{},{:.1},{},{},{},{},{},{},{},{},{},{},{},{},{}",
+        strategy.name(),
+        seed,
+        simulation.elapsed_seconds(),
+        event,
+        ledger.get(ResourceKind::Coins),
+        ledger.get(ResourceKind::Gems),
+        ledger.get(ResourceKind::Energy),
+        nodes.len(),
+        count(|n| matches!(n, NodeType::Gain { .. })),
+        count(|n| matches!(n, NodeType::Save { .. })),
+        count(|n| matches!(n, NodeType::Blocker { .. })),
+        count(|n| matches!(n, NodeType::Multiplier { .. })),
+        count(|n| matches!(n, NodeType::Converter { .. })),
+        count(|n| matches!(n, NodeType::Gate { .. })),
+        count(|n| matches!(n, NodeType::Bank { .. })),
+        count(|n| matches!(n, NodeType::Overclock { .. })),
+    );
+}
@@ -0,0 +1,229 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::automation::{ActivateNode, NodeActivation};
+use crate::balance::Balance;
+use crate::big_number::BigNumber;
+use crate::idle_gains::{Ledger, ResourceKind};
+use crate::income_stats::ResourceGained;
+use crate::number_format::format_number;
+use crate::progress::{NodeTextValidate, NodeTimer, Progress};
+use crate::settings::Settings;
+use crate::upgrade_shop::NodeUpgrades;
+use crate::{InheritedBlockStatus, NodeCurrencyGain, NodeManualBlockToggle};
+
+/// Nodes closer than this to a converter can be targeted by its effect.
+pub const CONVERTER_RADIUS: f32 = 300f32;
+
+pub struct ConverterPlugin;
+
+impl Plugin for ConverterPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(node_converter_react.after(NodeActivation))
+            .add_system(update_converter_label);
+    }
+}
+
+/// What a converter makes of the coins it consumes.
+#[derive(Reflect, FromReflect, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConverterOutput {
+    /// Produces [`NodeConverter::produced`] of this resource.
+    Resource(ResourceKind),
+    /// Finishes the timer of the nearest blocked node.
+    FinishTimer,
+    /// Adds a yield level to the nearest gain.
+    AddLevel,
+}
+
+impl ConverterOutput {
+    /// Applies a [`FinishTimer`](ConverterOutput::FinishTimer)
+    /// or [`AddLevel`](ConverterOutput::AddLevel) output to its target.
+    pub fn apply(self, timer: &mut impl NodeTimer, upgrades: &mut NodeUpgrades) {
+        match self {
+            ConverterOutput::Resource(_) => {}
+            ConverterOutput::FinishTimer => timer.finish(),
+            ConverterOutput::AddLevel => upgrades.yield_level += 1,
+        }
+    }
+}
+
+/// A node a converter may target, converters and blockers are never targeted.
+pub struct ConverterTarget {
+    pub pos: Vec2,
+    pub is_blocked: bool,
+    pub is_finished: bool,
+    pub is_gain: bool,
+}
+
+/// Consumes [`NodeConverter::cost`] coins when clicked ready, if its output has a target.
+#[derive(Component)]
+pub struct NodeConverter {
+    pub level: u32,
+    pub output: ConverterOutput,
+}
+
+impl NodeConverter {
+    /// Coins consumed by the next conversion.
+    pub fn cost(&self, balance: &Balance) -> BigNumber {
+        let growth = BigNumber::from(balance.converter_cost_growth as f64);
+        (BigNumber::from(balance.converter_cost_base as f64) * growth.powf(self.level as f64))
+            .floor()
+    }
+
+    /// Amount of resource produced by a [`ConverterOutput::Resource`] conversion.
+    pub fn produced(&self) -> BigNumber {
+        BigNumber::from(self.level)
+    }
+
+    pub fn can_afford(&self, ledger: &Ledger, balance: &Balance) -> bool {
+        ledger.get(ResourceKind::Coins) >= self.cost(balance)
+    }
+
+    /// The nearest of `candidates` its output applies to, or `None` when there is none.
+    ///
+    /// A [`Resource`](ConverterOutput::Resource) output needs no target, it gives `Some(None)`.
+    pub fn pick_target<K>(
+        &self,
+        pos: Vec2,
+        candidates: impl Iterator<Item = (K, ConverterTarget)>,
+    ) -> Option<Option<K>> {
+        let targets = candidates.map(|(key, target)| (target.pos, (key, target)));
+        let target = match self.output {
+            ConverterOutput::Resource(_) => return Some(None),
+            ConverterOutput::FinishTimer => nearest_in_reach(
+                pos,
+                targets.filter(|(_, (_, target))| target.is_blocked && !target.is_finished),
+            ),
+            ConverterOutput::AddLevel => {
+                nearest_in_reach(pos, targets.filter(|(_, (_, target))| target.is_gain))
+            }
+        };
+        target.map(|(key, _)| Some(key))
+    }
+
+    /// Pays for a conversion applied to its target, and waits before the next one.
+    ///
+    /// Returns what a [`Resource`](ConverterOutput::Resource) output produced.
+    pub fn convert(
+        &mut self,
+        timer: &mut impl NodeTimer,
+        upgrades: &NodeUpgrades,
+        ledger: &mut Ledger,
+        balance: &Balance,
+    ) -> Option<(ResourceKind, BigNumber)> {
+        let produced = match self.output {
+            ConverterOutput::Resource(resource) => {
+                ledger.add(resource, self.produced());
+                Some((resource, self.produced()))
+            }
+            _ => None,
+        };
+        ledger.add(ResourceKind::Coins, -self.cost(balance));
+        timer.restart(balance.timer_converter * upgrades.duration_factor());
+        self.level += 1;
+        produced
+    }
+
+    pub fn label(&self, balance: &Balance, settings: &Settings) -> String {
+        let cost = format_number(self.cost(balance), settings.notation);
+        let output = match self.output {
+            ConverterOutput::Resource(resource) => format!(
+                "{} {}",
+                format_number(self.produced(), settings.notation),
+                resource.name()
+            ),
+            ConverterOutput::FinishTimer => "finish".to_string(),
+            ConverterOutput::AddLevel => "+1 yield".to_string(),
+        };
+        format!("{}c > {}", cost, output)
+    }
+}
+
+/// Of `candidates` in reach of `pos`, the closest one.
+pub fn nearest_in_reach<T>(pos: Vec2, candidates: impl Iterator<Item = (Vec2, T)>) -> Option<T> {
+    candidates
+        .map(|(candidate_pos, candidate)| (candidate_pos.distance_squared(pos), candidate))
+        .filter(|(distance_squared, _)| *distance_squared <= CONVERTER_RADIUS * CONVERTER_RADIUS)
+        .min_by(|(a, _), (b, _)| a.total_cmp(b))
+        .map(|(_, candidate)| candidate)
+}
+
+fn node_converter_react(
+    mut events: EventReader<ActivateNode>,
+    mut q_converters: Query<(
+        &Transform,
+        &mut Progress,
+        &mut NodeConverter,
+        &NodeUpgrades,
+        &InheritedBlockStatus,
+    )>,
+    // Blockers finishing their timer spawn a node, converters leave them alone.
+    mut q_targets: Query<
+        (
+            Entity,
+            &Transform,
+            &mut Progress,
+            &mut NodeUpgrades,
+            &InheritedBlockStatus,
+            Option<&NodeCurrencyGain>,
+        ),
+        (Without<NodeConverter>, Without<NodeManualBlockToggle>),
+    >,
+    mut ledger: ResMut<Ledger>,
+    mut events_gained: EventWriter<ResourceGained>,
+    balance: Res<Balance>,
+) {
+    for event in events.iter() {
+        let e = event.0;
+        let Ok((transform, mut p, mut converter, upgrades, status)) = q_converters.get_mut(e)
+        else {
+            continue;
+        };
+        if status.is_blocked || !p.timer.finished() {
+            continue;
+        }
+        if !converter.can_afford(&ledger, &balance) {
+            continue;
+        }
+        let candidates = q_targets
+            .iter()
+            .map(|(e, transform, progress, _, status, gain)| {
+                let target = ConverterTarget {
+                    pos: transform.translation.truncate(),
+                    is_blocked: status.is_blocked,
+                    is_finished: progress.timer.finished(),
+                    is_gain: gain.is_some(),
+                };
+                (e, target)
+            });
+        let Some(target) = converter.pick_target(transform.translation.truncate(), candidates)
+        else {
+            continue;
+        };
+        if let Some(Ok((_, _, mut target_progress, mut target_upgrades, ..))) =
+            target.map(|e| q_targets.get_mut(e))
+        {
+            converter
+                .output
+                .apply(&mut *target_progress, &mut target_upgrades);
+        }
+        if let Some((resource, amount)) =
+            converter.convert(&mut *p, upgrades, &mut ledger, &balance)
+        {
+            events_gained.send(ResourceGained { resource, amount });
+        }
+    }
+}
+
+fn update_converter_label(
+    balance: Res<Balance>,
+    settings: Res<Settings>,
+    mut q_converters: Query<(Ref<NodeConverter>, &mut NodeTextValidate)>,
+) {
+    let refresh_all = balance.is_changed() || settings.is_changed();
+    for (converter, mut text) in q_converters.iter_mut() {
+        if refresh_all || converter.is_changed() {
+            text.text = converter.label(&balance, &settings);
+        }
+    }
+}
@@ -3,7 +3,9 @@ use std::string;
 use bevy::{prelude::*, transform};
 use bevy_easings::Ease;
 
-use crate::idle_gains::Currency;
+use crate::idle_gains::{Ledger, ResourceKind};
+use crate::number_format::format_number;
+use crate::settings::Settings;
 
 pub struct CurrencyPlugin;
 
@@ -19,43 +21,73 @@ struct FpsText;
 
 // A unit struct to help identify the color-changing Text component
 #[derive(Component)]
-struct CurrencyText;
+struct CurrencyText(ResourceKind);
 
 fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
-    let style = TextStyle {
-        font: asset_server.load("fonts/FiraSans-Bold.ttf"),
-        font_size: 100.0,
-        color: Color::WHITE,
-    };
-    commands.spawn((
-        TextBundle::from_sections(vec![
-            TextSection::new("coins:", style.clone()),
-            TextSection::new("0", style.clone()),
-        ])
-        // Set the alignment of the Text
-        .with_text_alignment(TextAlignment::Center)
-        // Set the style of the TextBundle itself.
-        .with_style(Style {
-            position_type: PositionType::Absolute,
-            position: UiRect {
-                top: Val::Px(5.0),
-                right: Val::Px(15.0),
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    top: Val::Px(5.0),
+                    right: Val::Px(15.0),
+                    ..default()
+                },
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::FlexEnd,
                 ..default()
             },
             ..default()
-        }),
-        CurrencyText,
-    ));
+        })
+        .with_children(|parent| {
+            for kind in ResourceKind::ALL {
+                // Coins are the main resource, others only show up once gathered.
+                let is_main = kind == ResourceKind::Coins;
+                let style = TextStyle {
+                    font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                    font_size: if is_main { 100.0 } else { 50.0 },
+                    color: Color::WHITE,
+                };
+                parent.spawn((
+                    TextBundle::from_sections(vec![
+                        TextSection::new(format!("{}:", kind.name()), style.clone()),
+                        TextSection::new("0", style.clone()),
+                    ])
+                    // Set the alignment of the Text
+                    .with_text_alignment(TextAlignment::Center)
+                    .with_style(Style {
+                        display: if is_main {
+                            Display::Flex
+                        } else {
+                            Display::None
+                        },
+                        ..default()
+                    }),
+                    CurrencyText(kind),
+                ));
+            }
+        });
 }
 
 fn currency_change_react(
     mut commands: Commands,
-    currency: Res<Currency>,
-    mut query: Query<(Entity, &Transform, &mut Text), With<CurrencyText>>,
+    ledger: Res<Ledger>,
+    settings: Res<Settings>,
+    mut previous: Local<Ledger>,
+    mut query: Query<(Entity, &Transform, &mut Text, &mut Style, &CurrencyText)>,
 ) {
-    if currency.is_changed() {
-        for (e, transform, mut text) in query.iter_mut() {
-            text.sections[1].value = currency.amount.to_string();
+    if !ledger.is_changed() && !settings.is_changed() {
+        return;
+    }
+    for (e, transform, mut text, mut style, CurrencyText(kind)) in query.iter_mut() {
+        let amount = ledger.get(*kind);
+        text.sections[1].value = format_number(amount, settings.notation);
+        style.display = if *kind == ResourceKind::Coins || !amount.is_zero() {
+            Display::Flex
+        } else {
+            Display::None
+        };
+        if amount != previous.get(*kind) {
             commands.entity(e).insert(
                 transform
                     .ease_to(
@@ -85,4 +117,5 @@ fn currency_change_react(
             );
         }
     }
+    *previous = ledger.clone();
 }
@@ -0,0 +1,165 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::automation::{ActivateNode, NodeActivation};
+use crate::balance::Balance;
+use crate::progress::{NodeTextValidate, NodeTimer, Progress};
+use crate::upgrade_shop::NodeUpgrades;
+
+pub struct GatePlugin;
+
+impl Plugin for GatePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(node_gate_react.after(NodeActivation));
+    }
+}
+
+/// How a gate combines its inputs, its [`Blockers`](crate::Blockers).
+#[derive(Reflect, FromReflect, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GateKind {
+    /// Blocks when all inputs block.
+    And,
+    /// Blocks when any input blocks, like a regular node.
+    Or,
+    /// Blocks when no input blocks.
+    Not,
+    /// Blocks when an odd number of inputs block.
+    Xor,
+}
+
+impl GateKind {
+    /// Clicking a gate cycles through kinds.
+    pub fn next(self) -> GateKind {
+        match self {
+            GateKind::And => GateKind::Or,
+            GateKind::Or => GateKind::Not,
+            GateKind::Not => GateKind::Xor,
+            GateKind::Xor => GateKind::And,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            GateKind::And => "AND",
+            GateKind::Or => "OR",
+            GateKind::Not => "NOT",
+            GateKind::Xor => "XOR",
+        }
+    }
+
+    /// Whether the gate blocks its [`ToBlock`](crate::ToBlock), from whether each input blocks.
+    ///
+    /// A gate without inputs never blocks.
+    pub fn output(self, inputs: impl IntoIterator<Item = bool>) -> bool {
+        let (count, blocking) = inputs.into_iter().fold((0, 0), |(count, blocking), input| {
+            (count + 1, blocking + input as usize)
+        });
+        if count == 0 {
+            return false;
+        }
+        match self {
+            GateKind::And => blocking == count,
+            GateKind::Or => blocking > 0,
+            GateKind::Not => blocking == 0,
+            GateKind::Xor => blocking % 2 == 1,
+        }
+    }
+}
+
+/// Replaces the blockers of the node it spawned from, they become its inputs.
+///
+/// A gate is never blocked itself, its [`SelfBlockStatus`](crate::SelfBlockStatus) is its output.
+#[derive(Component)]
+pub struct NodeGate {
+    pub kind: GateKind,
+}
+
+impl NodeGate {
+    /// Switches to the next kind when clicked ready.
+    pub fn click(
+        &mut self,
+        timer: &mut impl NodeTimer,
+        upgrades: &NodeUpgrades,
+        balance: &Balance,
+    ) {
+        self.kind = self.kind.next();
+        timer.restart(balance.timer_gate * upgrades.duration_factor());
+    }
+}
+
+fn node_gate_react(
+    mut events: EventReader<ActivateNode>,
+    mut q_gates: Query<(
+        &mut Progress,
+        &mut NodeGate,
+        &mut NodeTextValidate,
+        &NodeUpgrades,
+    )>,
+    balance: Res<Balance>,
+) {
+    for event in events.iter() {
+        let Ok((mut p, mut gate, mut text, upgrades)) = q_gates.get_mut(event.0) else {
+            continue;
+        };
+        if !p.timer.finished() {
+            continue;
+        }
+        gate.click(&mut *p, upgrades, &balance);
+        text.text = gate.kind.name().to_string();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn output() {
+        let inputs: [&[bool]; 5] = [
+            &[false],
+            &[true],
+            &[false, false],
+            &[true, false],
+            &[true, true],
+        ];
+        for (kind, expected) in [
+            (GateKind::And, [false, true, false, false, true]),
+            (GateKind::Or, [false, true, false, true, true]),
+            (GateKind::Not, [true, false, true, false, false]),
+            (GateKind::Xor, [false, true, false, true, false]),
+        ] {
+            for (inputs, expected) in inputs.iter().zip(expected) {
+                assert_eq!(
+                    kind.output(inputs.iter().copied()),
+                    expected,
+                    "{:?} of {:?}",
+                    kind,
+                    inputs
+                );
+            }
+        }
+        assert!(GateKind::Xor.output([true, true, true, false]));
+    }
+
+    #[test]
+    fn no_inputs_never_blocks() {
+        for kind in [GateKind::And, GateKind::Or, GateKind::Not, GateKind::Xor] {
+            assert!(!kind.output([]), "{:?}", kind);
+        }
+    }
+
+    #[test]
+    fn next_cycles_through_all_kinds() {
+        let mut kind = GateKind::And;
+        let mut seen = vec![];
+        for _ in 0..4 {
+            seen.push(kind);
+            kind = kind.next();
+        }
+        assert_eq!(kind, GateKind::And);
+        assert_eq!(
+            seen,
+            [GateKind::And, GateKind::Or, GateKind::Not, GateKind::Xor]
+        );
+    }
+}
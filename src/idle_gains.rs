@@ -1,6 +1,75 @@
+use std::collections::BTreeMap;
+
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::big_number::BigNumber;
+
+/// Kinds of resources the player can gather.
+#[derive(
+    Reflect,
+    FromReflect,
+    Serialize,
+    Deserialize,
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+)]
+pub enum ResourceKind {
+    #[default]
+    Coins,
+    Gems,
+    Energy,
+}
+
+impl ResourceKind {
+    pub const ALL: [ResourceKind; 3] = [
+        ResourceKind::Coins,
+        ResourceKind::Gems,
+        ResourceKind::Energy,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ResourceKind::Coins => "coins",
+            ResourceKind::Gems => "gems",
+            ResourceKind::Energy => "energy",
+        }
+    }
+
+    /// Text shown on a ready gain node producing this resource.
+    pub fn gain_text(self) -> &'static str {
+        match self {
+            ResourceKind::Coins => "Gain!",
+            ResourceKind::Gems => "Gems!",
+            ResourceKind::Energy => "Energy!",
+        }
+    }
+}
+
+/// Amount of each resource owned, missing resources count as 0.
+#[derive(Resource, Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(transparent)]
+pub struct Ledger {
+    amounts: BTreeMap<ResourceKind, BigNumber>,
+}
+
+impl Ledger {
+    pub fn get(&self, kind: ResourceKind) -> BigNumber {
+        self.amounts.get(&kind).copied().unwrap_or_default()
+    }
+
+    pub fn set(&mut self, kind: ResourceKind, amount: BigNumber) {
+        self.amounts.insert(kind, amount);
+    }
 
-#[derive(Debug, Resource, Default)]
-pub struct Currency {
-    pub amount: i32,
+    pub fn add(&mut self, kind: ResourceKind, amount: BigNumber) {
+        *self.amounts.entry(kind).or_default() += amount;
+    }
 }
@@ -0,0 +1,259 @@
+use std::collections::VecDeque;
+
+use bevy::{input::common_conditions::input_just_pressed, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::big_number::BigNumber;
+use crate::idle_gains::{Ledger, ResourceKind};
+use crate::number_format::format_number;
+use crate::save_slots::is_typing;
+use crate::settings::Settings;
+use crate::MapAssets;
+
+/// Duration of a sample of the recent history.
+const SHORT_INTERVAL_SECONDS: f32 = 10f32;
+/// 10 minutes of recent history.
+const SHORT_SAMPLES: usize = 60;
+/// Short samples merged into each long sample, 5 minutes.
+const SHORT_PER_LONG: u32 = 30;
+/// 6 hours of long history.
+const LONG_SAMPLES: usize = 72;
+
+const GRAPH_HEIGHT: f32 = 60f32;
+const GRAPH_BAR_WIDTH: f32 = 4f32;
+
+pub struct IncomeStatsPlugin;
+
+impl Plugin for IncomeStatsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<IncomeStats>()
+            .init_resource::<IncomePanel>()
+            .add_event::<ResourceGained>()
+            .add_system(track_income)
+            .add_system(
+                toggle_panel
+                    .run_if(input_just_pressed(KeyCode::G))
+                    .run_if(not(is_typing)),
+            )
+            .add_system(update_panel_ui.after(track_income));
+    }
+}
+
+/// Sent whenever resources are produced, to measure income.
+pub struct ResourceGained {
+    pub resource: ResourceKind,
+    pub amount: BigNumber,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default)]
+pub struct IncomeSample {
+    /// Coins owned at the end of the sample.
+    pub coins: BigNumber,
+    /// Coins gained during the sample.
+    pub gained: BigNumber,
+}
+
+/// Rolling coins history, oldest sample first.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct IncomeHistory {
+    /// One sample per [`SHORT_INTERVAL_SECONDS`].
+    pub short: VecDeque<IncomeSample>,
+    /// One sample per [`SHORT_PER_LONG`] short samples.
+    pub long: VecDeque<IncomeSample>,
+    /// Short samples since the last long sample.
+    short_since_long: u32,
+}
+
+#[derive(Resource, Default, Debug)]
+pub struct IncomeStats {
+    /// Coins per second during the last sample.
+    pub current_per_second: BigNumber,
+    /// Coins per second over the whole short history.
+    pub average_per_second: BigNumber,
+    pub history: IncomeHistory,
+}
+
+impl IncomeStats {
+    /// Replaces the history with a loaded one.
+    pub fn restore(&mut self, history: IncomeHistory) {
+        *self = IncomeStats {
+            history,
+            ..default()
+        };
+        self.update_rates();
+    }
+
+    fn push_sample(&mut self, coins: BigNumber, gained: BigNumber) {
+        let sample = IncomeSample { coins, gained };
+        let history = &mut self.history;
+        history.short.push_back(sample);
+        if history.short.len() > SHORT_SAMPLES {
+            history.short.pop_front();
+        }
+        history.short_since_long += 1;
+        if history.short_since_long >= SHORT_PER_LONG {
+            let gained = history
+                .short
+                .iter()
+                .rev()
+                .take(history.short_since_long as usize)
+                .fold(BigNumber::ZERO, |sum, sample| sum + sample.gained);
+            history.long.push_back(IncomeSample { coins, gained });
+            if history.long.len() > LONG_SAMPLES {
+                history.long.pop_front();
+            }
+            history.short_since_long = 0;
+        }
+        self.update_rates();
+    }
+
+    fn update_rates(&mut self) {
+        let interval = BigNumber::from(SHORT_INTERVAL_SECONDS as f64);
+        self.current_per_second = self
+            .history
+            .short
+            .back()
+            .map_or(BigNumber::ZERO, |sample| sample.gained / interval);
+        let total = self
+            .history
+            .short
+            .iter()
+            .fold(BigNumber::ZERO, |sum, sample| sum + sample.gained);
+        let duration = interval * BigNumber::from(self.history.short.len() as f64);
+        self.average_per_second = total / duration;
+    }
+}
+
+fn track_income(
+    time: Res<Time>,
+    ledger: Res<Ledger>,
+    mut stats: ResMut<IncomeStats>,
+    mut events: EventReader<ResourceGained>,
+    mut gained: Local<BigNumber>,
+    mut elapsed_seconds: Local<f32>,
+) {
+    for event in events.iter() {
+        if event.resource == ResourceKind::Coins {
+            *gained += event.amount;
+        }
+    }
+    *elapsed_seconds += time.delta_seconds();
+    if *elapsed_seconds >= SHORT_INTERVAL_SECONDS {
+        *elapsed_seconds -= SHORT_INTERVAL_SECONDS;
+        stats.push_sample(ledger.get(ResourceKind::Coins), *gained);
+        *gained = BigNumber::ZERO;
+    }
+}
+
+#[derive(Resource, Default)]
+struct IncomePanel {
+    is_open: bool,
+}
+
+#[derive(Component)]
+struct IncomePanelNode;
+
+fn toggle_panel(mut panel: ResMut<IncomePanel>) {
+    panel.is_open = !panel.is_open;
+}
+
+fn update_panel_ui(
+    mut commands: Commands,
+    map_assets: Res<MapAssets>,
+    panel: Res<IncomePanel>,
+    stats: Res<IncomeStats>,
+    settings: Res<Settings>,
+    q_panel: Query<Entity, With<IncomePanelNode>>,
+) {
+    // Stats only change when a sample is pushed.
+    if !panel.is_changed() && !stats.is_changed() && !settings.is_changed() {
+        return;
+    }
+    for panel in q_panel.iter() {
+        commands.entity(panel).despawn_recursive();
+    }
+    if !panel.is_open {
+        return;
+    }
+    let text_style = TextStyle {
+        color: Color::WHITE,
+        font_size: 20.0,
+        ..map_assets.text_style.clone()
+    };
+    let graph = |parent: &mut ChildBuilder, samples: &VecDeque<IncomeSample>| {
+        let max = samples
+            .iter()
+            .map(|sample| sample.coins)
+            .fold(
+                BigNumber::ZERO,
+                |max, coins| if coins > max { coins } else { max },
+            );
+        parent
+            .spawn(NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Row,
+                    align_items: AlignItems::FlexEnd,
+                    size: Size::height(Val::Px(GRAPH_HEIGHT)),
+                    margin: UiRect::vertical(Val::Px(4.0)),
+                    ..default()
+                },
+                background_color: Color::rgba(0.2, 0.2, 0.2, 0.9).into(),
+                ..default()
+            })
+            .with_children(|parent| {
+                for sample in samples.iter() {
+                    let ratio = (sample.coins / max).to_f32().clamp(0f32, 1f32);
+                    parent.spawn(NodeBundle {
+                        style: Style {
+                            size: Size::new(
+                                Val::Px(GRAPH_BAR_WIDTH),
+                                Val::Px((ratio * GRAPH_HEIGHT).max(1f32)),
+                            ),
+                            ..default()
+                        },
+                        background_color: Color::GREEN.into(),
+                        ..default()
+                    });
+                }
+            });
+    };
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        bottom: Val::Px(60.0),
+                        left: Val::Px(15.0),
+                        ..default()
+                    },
+                    flex_direction: FlexDirection::Column,
+                    padding: UiRect::all(Val::Px(10.0)),
+                    ..default()
+                },
+                background_color: Color::rgba(0.1, 0.1, 0.1, 0.9).into(),
+                ..default()
+            },
+            IncomePanelNode,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                format!(
+                    "income: {}/s, average {}/s (G)",
+                    format_number(stats.current_per_second, settings.notation),
+                    format_number(stats.average_per_second, settings.notation)
+                ),
+                text_style.clone(),
+            ));
+            parent.spawn(TextBundle::from_section(
+                "coins, last 10 minutes",
+                text_style.clone(),
+            ));
+            graph(parent, &stats.history.short);
+            parent.spawn(TextBundle::from_section(
+                "coins, last 6 hours",
+                text_style.clone(),
+            ));
+            graph(parent, &stats.history.long);
+        });
+}
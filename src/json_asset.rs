@@ -0,0 +1,123 @@
+use std::marker::PhantomData;
+
+use bevy::{
+    asset::{Asset, AssetLoader, LoadContext, LoadedAsset},
+    prelude::*,
+    utils::BoxedFuture,
+};
+use serde::de::DeserializeOwned;
+
+/// Checks run on a loaded file before it replaces the resource.
+pub trait ValidateAsset {
+    /// Why the file can't be used, the resource then keeps its previous value.
+    fn validate(&self) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+/// Loads a json tuning file into the resource `T`, keeping `T::default()` until loaded.
+///
+/// Edits of the file are applied while the game runs, when assets are watched.
+pub struct JsonAssetPlugin<T> {
+    path: &'static str,
+    extensions: &'static [&'static str],
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T> JsonAssetPlugin<T> {
+    /// `extensions` should be specific to `T`, like `["balance.json"]`.
+    pub fn new(path: &'static str, extensions: &'static [&'static str]) -> Self {
+        Self {
+            path,
+            extensions,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<T> Plugin for JsonAssetPlugin<T>
+where
+    T: Asset + Resource + DeserializeOwned + Clone + Default + ValidateAsset,
+{
+    fn build(&self, app: &mut App) {
+        let path = self.path;
+        app.add_asset::<T>()
+            .add_asset_loader(JsonLoader::<T> {
+                extensions: self.extensions,
+                marker: PhantomData,
+            })
+            .init_resource::<T>()
+            .add_startup_system(
+                move |mut commands: Commands, asset_server: Res<AssetServer>| {
+                    commands.insert_resource(JsonAssetHandle::<T>(asset_server.load(path)));
+                },
+            )
+            .add_system(apply_asset::<T>);
+    }
+}
+
+struct JsonLoader<T> {
+    extensions: &'static [&'static str],
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T> AssetLoader for JsonLoader<T>
+where
+    T: Asset + DeserializeOwned,
+{
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let asset = serde_json::from_slice::<T>(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(asset));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        self.extensions
+    }
+}
+
+#[derive(Resource)]
+struct JsonAssetHandle<T: Asset>(Handle<T>);
+
+/// Copies the asset into its resource, when loaded and on each reload, if it is valid.
+fn apply_asset<T: Asset + Resource + Clone + ValidateAsset>(
+    handle: Option<Res<JsonAssetHandle<T>>>,
+    assets: Res<Assets<T>>,
+    mut events: EventReader<AssetEvent<T>>,
+    mut resource: ResMut<T>,
+) {
+    let Some(handle) = handle else {
+        return;
+    };
+    for event in events.iter() {
+        match event {
+            AssetEvent::Created { handle: changed } | AssetEvent::Modified { handle: changed }
+                if *changed == handle.0 =>
+            {
+                let Some(loaded) = assets.get(&handle.0) else {
+                    continue;
+                };
+                match loaded.validate() {
+                    Ok(()) => {
+                        info!("loaded {}", std::any::type_name::<T>());
+                        *resource = loaded.clone();
+                    }
+                    Err(err) => {
+                        warn!(
+                            "ignoring {}, keeping the previous one: {}",
+                            std::any::type_name::<T>(),
+                            err
+                        );
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
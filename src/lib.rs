@@ -0,0 +1,780 @@
+// Bevy code commonly triggers these lints and they may be important signals
+// about code quality. They are sometimes hard to avoid though, and the CI
+// workflow treats them as errors, so this allows them throughout the project.
+// Feel free to delete this line.
+#![allow(clippy::too_many_arguments, clippy::type_complexity)]
+
+use automation::{AutomationPlugin, NodeActivation};
+use autosave::AutosavePlugin;
+use balance::{Balance, BalancePlugin};
+use bank::{BankPlugin, NodeBank};
+use bevy::input::common_conditions::input_just_pressed;
+use bevy::{ecs::query::WorldQuery, prelude::*, sprite::Mesh2dHandle, utils::HashMap};
+use bevy_easings::EasingsPlugin;
+use bevy_mod_picking::prelude::*;
+use bevy_pancam::{PanCam, PanCamPlugin};
+use bevy_pkv::PkvStore;
+use bevy_prototype_debug_lines::{DebugLines, DebugLinesPlugin};
+use big_number::BigNumber;
+use converter::{ConverterPlugin, NodeConverter};
+use currency::CurrencyPlugin;
+use gate::{GateKind, GatePlugin, NodeGate};
+use idle_gains::{Ledger, ResourceKind};
+use income_stats::IncomeStatsPlugin;
+use multiplier::{MultiplierPlugin, NodeMultiplier};
+use new_node::*;
+use number_format::NumberFormatPlugin;
+use offline_progress::{OfflineProgress, OfflineProgressPlugin};
+use overclock::{NodeOverclock, OverclockPlugin};
+use persisted_game::{EventLoad, GameLoader, LoadError, NodeType, ProtectedSlots};
+use prestige::{Prestige, PrestigePlugin};
+use progress::Progress;
+use save_history::SaveHistoryPlugin;
+use save_slots::{is_typing, SaveSlots, SaveSlotsPlugin};
+use save_transfer::SaveTransferPlugin;
+use save_validation::{SaveProblem, SaveProblems, SaveValidationPlugin};
+use spawn_table::SpawnTablePlugin;
+use upgrade_shop::{NodeUpgrades, UpgradeShopPlugin};
+
+pub mod automation;
+mod autosave;
+pub mod balance;
+mod bank;
+pub mod big_number;
+mod converter;
+mod currency;
+mod gate;
+pub mod idle_gains;
+mod income_stats;
+mod json_asset;
+pub mod multiplier;
+mod new_node;
+mod number_format;
+mod offline_progress;
+mod overclock;
+pub mod persisted_game;
+use picking::{node_manual_toggle_block_react, node_save_react};
+
+use picking_aabb::AabbBackend;
+use progress::*;
+
+use status_visual::update_status_visual;
+
+//pub mod persisted_game;
+pub mod picking;
+mod picking_aabb;
+mod poisson;
+mod prestige;
+mod progress;
+mod save_history;
+mod save_migration;
+mod save_slots;
+mod save_transfer;
+mod save_validation;
+mod settings;
+pub mod simulation;
+pub mod spawn_table;
+mod status_visual;
+pub mod timer_material;
+pub mod upgrade_shop;
+
+use timer_material::{TimerMaterial, TimerMaterialPlugin, TimerMaterials};
+
+pub fn run() {
+    App::new()
+        .register_type::<Blockers>()
+        .register_type::<ToBlock>()
+        .add_plugins(
+            DefaultPlugins
+                .set(WindowPlugin {
+                    primary_window: Some(Window {
+                        //resolution: WindowResolution::new(640., 640.).with_scale_factor_override(1.0),
+                        ..default()
+                    }),
+                    ..default()
+                })
+                // Hot reloads the balance file.
+                .set(AssetPlugin {
+                    watch_for_changes: cfg!(not(target_arch = "wasm32")),
+                    ..default()
+                }),
+        )
+        .add_plugins(
+            DefaultPickingPlugins
+                .build()
+                .disable::<DebugPickingPlugin>(),
+        ) // <- Adds picking, interaction, and highlighting
+        .add_plugin(BalancePlugin)
+        .add_plugin(SpawnTablePlugin)
+        .add_plugin(GameLoader)
+        .add_plugin(SaveSlotsPlugin)
+        .add_plugin(SaveTransferPlugin)
+        .add_plugin(AutosavePlugin)
+        .add_plugin(SaveHistoryPlugin)
+        .add_plugin(SaveValidationPlugin)
+        .add_plugin(CurrencyPlugin)
+        .add_plugin(NumberFormatPlugin)
+        .add_plugin(UpgradeShopPlugin)
+        .add_plugin(AutomationPlugin)
+        .add_plugin(MultiplierPlugin)
+        .add_plugin(ConverterPlugin)
+        .add_plugin(GatePlugin)
+        .add_plugin(BankPlugin)
+        .add_plugin(OverclockPlugin)
+        .add_plugin(IncomeStatsPlugin)
+        .add_plugin(PrestigePlugin)
+        .add_plugin(OfflineProgressPlugin)
+        .add_plugin(AabbBackend)
+        //.add_plugin(WorldInspectorPlugin::new())
+        //.add_plugin(DebugEventsPickingPlugin) // <- Adds debug event logging.
+        //.add_plugin(LogDiagnosticsPlugin::default())
+        //.add_plugin(FrameTimeDiagnosticsPlugin::default())
+        .add_plugin(PanCamPlugin::default())
+        .add_plugin(DebugLinesPlugin::default())
+        .add_plugin(EasingsPlugin)
+        .add_plugin(TimerMaterialPlugin)
+        .add_event::<NewNodeEvent>()
+        .add_event::<PropagateResetManualButtons>()
+        .init_resource::<Ledger>()
+        .add_startup_system(picking::setup)
+        .add_system(
+            setup
+                .in_schedule(CoreSchedule::Startup)
+                .in_base_set(StartupSet::PostStartup),
+        )
+        .add_system(load)
+        .add_system(
+            reload_current_slot
+                .run_if(input_just_pressed(KeyCode::L))
+                .run_if(not(is_typing)),
+        )
+        /*        .add_system(
+            spawn_map_empty
+                .in_schedule(CoreSchedule::Startup)
+                .in_base_set(StartupSet::PostStartup),
+        )*/
+        .add_system(update_progress_timer)
+        .add_system(update_progress_manual_auto_block)
+        .add_system(picking::node_gain_react.after(NodeActivation))
+        .add_system(reset_manual_button_timers.after(picking::node_gain_react))
+        .add_system(node_manual_toggle_block_react.after(NodeActivation))
+        .add_system(node_save_react.after(NodeActivation))
+        .add_system(new_button)
+        .add_system(
+            check_self_block
+                .after(new_button)
+                .after(reset_manual_button_timers),
+        )
+        .add_system(update_inherited_block_status.after(check_self_block))
+        .add_system(update_progress_text.after(update_inherited_block_status))
+        .add_system(update_progress_material.after(update_inherited_block_status))
+        .add_system(draw_relations.before(new_button))
+        .add_system(update_status_visual.after(update_inherited_block_status))
+        .run();
+}
+
+fn load(
+    mut commands: Commands,
+    mut pkv: ResMut<PkvStore>,
+    mut events: EventReader<EventLoad>,
+    mut offline_progress: EventWriter<OfflineProgress>,
+    mut problems: ResMut<SaveProblems>,
+    mut protected: ResMut<ProtectedSlots>,
+    balance: Res<Balance>,
+) {
+    for event in events.iter() {
+        let mut load_problems = Vec::new();
+        let mut to_load = match persisted_game::load(&mut pkv, event.slot) {
+            Ok(save) => {
+                protected.slots.remove(&event.slot);
+                save
+            }
+            Err(err) => {
+                error!("{}", err);
+                // Without a backup, autosaving the new map would lose the stored one.
+                if let LoadError::Unreadable(_) = err {
+                    protected.slots.insert(event.slot);
+                }
+                load_problems.push(SaveProblem::NotLoaded {
+                    slot: event.slot,
+                    reason: err.to_string(),
+                });
+                persisted_game::default_save()
+            }
+        };
+        offline_progress.send(offline_progress::fast_forward(
+            &mut to_load,
+            persisted_game::now_since2023(),
+            &balance,
+        ));
+        load_problems.extend(persisted_game::start_load(
+            &mut commands,
+            &to_load,
+            event.slot,
+        ));
+        problems.report("load", load_problems);
+    }
+}
+
+fn reload_current_slot(slots: Res<SaveSlots>, mut event_load: EventWriter<EventLoad>) {
+    event_load.send(EventLoad {
+        slot: slots.current,
+    });
+}
+
+#[derive(Component)]
+pub struct ButtonRef(pub Entity);
+
+// node behaviours
+
+#[derive(Component)]
+pub struct NodeManualBlockToggle {
+    pub is_blocked: bool,
+}
+
+impl NodeManualBlockToggle {
+    /// Blocks while its timer runs, then while toggled on.
+    pub fn is_self_blocked(&self, timer: &impl NodeTimer) -> bool {
+        !timer.is_finished() || self.is_blocked
+    }
+
+    /// Blocks again for a while, when a gain it blocks is clicked.
+    pub fn reset(
+        &mut self,
+        timer: &mut impl NodeTimer,
+        upgrades: &NodeUpgrades,
+        balance: &Balance,
+    ) {
+        self.is_blocked = true;
+        timer.restart(balance.timer_reset_blocker_fixed * upgrades.duration_factor());
+    }
+}
+
+#[derive(Component)]
+pub struct NodeCurrencyGain {
+    level: u32,
+    resource: ResourceKind,
+}
+
+impl NodeCurrencyGain {
+    /// Produces its resource when clicked ready, boosted by `boost`, and returns how much.
+    ///
+    /// The next wait grows with the amount owned.
+    pub fn click(
+        &mut self,
+        timer: &mut impl NodeTimer,
+        upgrades: &NodeUpgrades,
+        boost: BigNumber,
+        ledger: &mut Ledger,
+        prestige: &Prestige,
+        balance: &Balance,
+    ) -> BigNumber {
+        let gained = upgrades.gain_yield() * prestige.yield_multiplier() * boost;
+        ledger.add(self.resource, gained);
+        let amount = ledger.get(self.resource);
+        timer.restart(
+            balance.gain_duration(amount, self.level)
+                * upgrades.duration_factor()
+                * prestige.timer_factor(),
+        );
+        self.level += 1;
+        gained
+    }
+}
+
+#[derive(Component)]
+pub struct NodeSave {
+    level: u32,
+}
+
+impl NodeSave {
+    /// Saves when clicked ready, the next wait grows with `coins`.
+    pub fn click(
+        &mut self,
+        timer: &mut impl NodeTimer,
+        upgrades: &NodeUpgrades,
+        coins: BigNumber,
+        balance: &Balance,
+    ) {
+        timer.restart(balance.save_duration(self.level, coins) * upgrades.duration_factor());
+        self.level += 1;
+    }
+}
+
+/// Behaviour components of a node, to know its [`NodeType`].
+#[derive(WorldQuery)]
+pub struct NodeTypeQuery {
+    gain: Option<&'static NodeCurrencyGain>,
+    save: Option<&'static NodeSave>,
+    multiplier: Option<&'static NodeMultiplier>,
+    converter: Option<&'static NodeConverter>,
+    gate: Option<&'static NodeGate>,
+    bank: Option<&'static NodeBank>,
+    overclock: Option<&'static NodeOverclock>,
+    self_status: &'static SelfBlockStatus,
+}
+
+impl NodeTypeQueryItem<'_> {
+    pub fn node_type(&self) -> NodeType {
+        if let Some(gain) = self.gain {
+            NodeType::Gain {
+                level: gain.level,
+                resource: gain.resource,
+            }
+        } else if let Some(save) = self.save {
+            NodeType::Save { level: save.level }
+        } else if let Some(multiplier) = self.multiplier {
+            NodeType::Multiplier {
+                level: multiplier.level,
+            }
+        } else if let Some(converter) = self.converter {
+            NodeType::Converter {
+                level: converter.level,
+                output: converter.output,
+            }
+        } else if let Some(gate) = self.gate {
+            NodeType::Gate { kind: gate.kind }
+        } else if let Some(bank) = self.bank {
+            NodeType::Bank {
+                level: bank.level,
+                deposit: bank.deposit,
+                rate: bank.rate,
+                cycles: bank.cycles,
+            }
+        } else if let Some(overclock) = self.overclock {
+            NodeType::Overclock {
+                level: overclock.level,
+                scope: overclock.scope,
+            }
+        } else {
+            // Blockers have no other behaviour component.
+            NodeType::Blocker {
+                is_blocked: self.self_status.is_blocked,
+            }
+        }
+    }
+}
+//
+
+/// To know which nodes are blocking our behaviour.
+#[derive(Reflect, Component)]
+pub struct Blockers {
+    pub entities: Vec<Entity>,
+}
+
+/// To know which nodes to block.
+#[derive(Reflect, Component)]
+pub struct ToBlock {
+    pub entities: Vec<Entity>,
+}
+
+#[derive(Component)]
+pub struct InheritedBlockStatus {
+    pub is_blocked: bool,
+}
+#[derive(Component)]
+pub struct SelfBlockStatus {
+    pub is_blocked: bool,
+}
+
+#[derive(Resource)]
+pub struct MapAssets {
+    pub font: Handle<Font>,
+    pub text_style: TextStyle,
+    pub mesh_gain: Mesh2dHandle,
+    pub mesh_save: Mesh2dHandle,
+    pub mesh_blocker: Mesh2dHandle,
+    pub mesh_multiplier: Mesh2dHandle,
+    pub mesh_converter: Mesh2dHandle,
+    pub mesh_gate: Mesh2dHandle,
+    pub mesh_bank: Mesh2dHandle,
+    pub mesh_overclock: Mesh2dHandle,
+    pub eye_catcher_mesh: Mesh2dHandle,
+    pub eye_catcher_material: Handle<ColorMaterial>,
+}
+
+fn setup(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut assets_timer: ResMut<Assets<TimerMaterial>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    commands.insert_resource(RandomForMap::default());
+    // 2d camera
+    commands.spawn((
+        Camera2dBundle::default(),
+        PanCam::default(),
+        RaycastPickCamera::default(),
+    ));
+    commands.spawn(TimerMaterials::new(&mut assets_timer, Color::GREEN, 180));
+    let font = asset_server.load("fonts/FiraSans-Bold.ttf");
+    let map_assets = MapAssets {
+        font: font.clone(),
+        text_style: TextStyle {
+            font,
+            font_size: 30.0,
+            color: Color::BLACK,
+        },
+        mesh_gain: meshes.add(Mesh::from(shape::Circle::default())).into(),
+        mesh_blocker: meshes.add(Mesh::from(shape::Quad::default())).into(),
+        mesh_save: meshes
+            .add(Mesh::from(shape::RegularPolygon::new(0.5f32, 3)))
+            .into(),
+        mesh_multiplier: meshes
+            .add(Mesh::from(shape::RegularPolygon::new(0.5f32, 5)))
+            .into(),
+        mesh_converter: meshes
+            .add(Mesh::from(shape::RegularPolygon::new(0.5f32, 8)))
+            .into(),
+        mesh_gate: meshes
+            .add(Mesh::from(shape::RegularPolygon::new(0.5f32, 4)))
+            .into(),
+        mesh_bank: meshes
+            .add(Mesh::from(shape::RegularPolygon::new(0.5f32, 7)))
+            .into(),
+        mesh_overclock: meshes
+            .add(Mesh::from(shape::Quad::new(Vec2::new(1.2f32, 0.6f32))))
+            .into(),
+        eye_catcher_mesh: meshes
+            .add(Mesh::from(shape::RegularPolygon::new(0.5f32, 6)))
+            .into(),
+        eye_catcher_material: materials.add(ColorMaterial::from(Color::YELLOW_GREEN)),
+    };
+
+    commands.insert_resource(map_assets);
+}
+
+fn check_self_block(
+    mut q_nodes: Query<
+        (&Progress, &mut NodeManualBlockToggle, &mut SelfBlockStatus),
+        Or<(Changed<Progress>, Changed<NodeManualBlockToggle>)>,
+    >,
+) {
+    for (p, manual, mut status) in q_nodes.iter_mut() {
+        status.is_blocked = manual.is_self_blocked(p);
+    }
+}
+
+/// A node as block propagation sees it, whether identified by an [`Entity`] or a save index.
+pub struct BlockingNode<'a, K> {
+    pub blockers: &'a [K],
+    pub gate: Option<GateKind>,
+    pub is_self_blocked: bool,
+}
+
+impl<K: Copy + Eq + std::hash::Hash> BlockingNode<'_, K> {
+    /// A node is blocked if any of its blockers blocks, by itself or by its own blockers.
+    ///
+    /// Gates are never blocked, they block from their inputs, see [`GateKind::output`].
+    pub fn is_inherited_blocked(&self, emits: &HashMap<K, bool>) -> bool {
+        self.gate.is_none()
+            && self
+                .blockers
+                .iter()
+                .any(|blocker| emits.get(blocker).copied().unwrap_or(false))
+    }
+}
+
+/// Whether each of `keys` and their blockers blocks the nodes it is wired to.
+///
+/// `node` returns `None` for a node gone, like a despawned one left in [`Blockers`]: it doesn't block.
+pub fn block_emits<'a, K: Copy + Eq + std::hash::Hash + 'a>(
+    keys: impl IntoIterator<Item = K>,
+    node: impl Fn(K) -> Option<BlockingNode<'a, K>>,
+) -> HashMap<K, bool> {
+    let mut emits = HashMap::new();
+    for key in keys {
+        recurse_block(&node, key, &mut emits);
+    }
+    emits
+}
+
+/// Whether `key` blocks the nodes it is wired to, memoized in `emits`.
+fn recurse_block<'a, K: Copy + Eq + std::hash::Hash + 'a>(
+    node: &impl Fn(K) -> Option<BlockingNode<'a, K>>,
+    key: K,
+    emits: &mut HashMap<K, bool>,
+) -> bool {
+    if let Some(is_blocking) = emits.get(&key) {
+        return *is_blocking;
+    }
+    // Guard against cycles: a node being computed doesn't block.
+    emits.insert(key, false);
+    let Some(blocking_node) = node(key) else {
+        return false;
+    };
+    let inputs = blocking_node
+        .blockers
+        .iter()
+        .map(|blocker| recurse_block(node, *blocker, emits));
+    let is_blocking = match blocking_node.gate {
+        Some(kind) => kind.output(inputs),
+        // Every input is computed, so all blockers end up in `emits`.
+        None => inputs.fold(blocking_node.is_self_blocked, |is_blocking, input| {
+            is_blocking || input
+        }),
+    };
+    emits.insert(key, is_blocking);
+    is_blocking
+}
+
+/// Applies [`block_emits`] to the map.
+fn update_inherited_block_status(
+    q_blockers: Query<(&Blockers, Option<&NodeGate>, Entity), With<BaseNode>>,
+    mut q_block_status: Query<(&mut InheritedBlockStatus, &mut SelfBlockStatus), With<BaseNode>>,
+) {
+    let blocking_node = |e: Entity| {
+        let (blockers, gate, _) = q_blockers.get(e).ok()?;
+        Some(BlockingNode {
+            blockers: &blockers.entities,
+            gate: gate.map(|gate| gate.kind),
+            is_self_blocked: q_block_status
+                .get(e)
+                .is_ok_and(|(_, self_status)| self_status.is_blocked),
+        })
+    };
+    let emits = block_emits(q_blockers.iter().map(|(.., e)| e), blocking_node);
+    let statuses: Vec<_> = q_blockers
+        .iter()
+        .map(|(_, gate, e)| {
+            let is_inherited_blocked =
+                blocking_node(e).is_some_and(|node| node.is_inherited_blocked(&emits));
+            (e, gate.is_some(), is_inherited_blocked)
+        })
+        .collect();
+    for (e, is_gate, is_inherited_blocked) in statuses {
+        let Ok((mut inherited_status, mut self_status)) = q_block_status.get_mut(e) else {
+            continue;
+        };
+        inherited_status.is_blocked = is_inherited_blocked;
+        if is_gate {
+            self_status.is_blocked = emits.get(&e).copied().unwrap_or(false);
+        }
+    }
+}
+
+pub struct PropagateResetManualButtons(pub Entity);
+
+fn reset_manual_button_timers(
+    balance: Res<Balance>,
+    mut events: EventReader<PropagateResetManualButtons>,
+    q_blockers: Query<(&Blockers, Entity), With<BaseNode>>,
+    mut q_manual_node: Query<
+        (
+            &mut NodeManualBlockToggle,
+            &mut SelfBlockStatus,
+            &mut Progress,
+            &NodeUpgrades,
+        ),
+        With<BaseNode>,
+    >,
+) {
+    for e in events.iter() {
+        recurse_reset_manual(&e.0, &mut q_manual_node, &q_blockers, &balance);
+    }
+}
+
+fn recurse_reset_manual(
+    e: &Entity,
+    q_manual_node: &mut Query<
+        (
+            &mut NodeManualBlockToggle,
+            &mut SelfBlockStatus,
+            &mut Progress,
+            &NodeUpgrades,
+        ),
+        With<BaseNode>,
+    >,
+    q_blockers: &Query<(&Blockers, Entity), With<BaseNode>>,
+    balance: &Balance,
+) {
+    if let Ok((mut manual, mut self_status, mut progress, upgrades)) = q_manual_node.get_mut(*e) {
+        manual.reset(&mut *progress, upgrades, balance);
+        self_status.is_blocked = true;
+    }
+    let Ok(blockers) = q_blockers.get(*e) else {
+        return;
+    };
+    for child in blockers.0.entities.iter() {
+        recurse_reset_manual(child, q_manual_node, q_blockers, balance);
+    }
+}
+
+fn draw_relations(
+    mut lines: ResMut<DebugLines>,
+    q_to_block: Query<(&ToBlock, Entity), With<BaseNode>>,
+    q_blockers: Query<
+        (
+            &Transform,
+            &Blockers,
+            Entity,
+            &InheritedBlockStatus,
+            &SelfBlockStatus,
+            Option<&NodeGate>,
+        ),
+        With<BaseNode>,
+    >,
+) {
+    for (to_blocks, e) in q_to_block.iter() {
+        if to_blocks.entities.is_empty() {
+            recurse_draw_relations(&mut lines, &q_blockers, e);
+        }
+    }
+}
+
+fn recurse_draw_relations(
+    lines: &mut ResMut<DebugLines>,
+    q_blockers: &Query<
+        (
+            &Transform,
+            &Blockers,
+            Entity,
+            &InheritedBlockStatus,
+            &SelfBlockStatus,
+            Option<&NodeGate>,
+        ),
+        With<BaseNode>,
+    >,
+    e: Entity,
+) {
+    if let Ok(blocked) = q_blockers.get(e) {
+        for new_blocker in blocked.1.entities.iter() {
+            // A despawned node may linger in `Blockers`.
+            let Ok(blocker_data) = q_blockers.get(*new_blocker) else {
+                continue;
+            };
+            lines.line_colored(
+                blocked.0.translation,
+                blocker_data.0.translation,
+                0f32,
+                match (
+                    blocked.5.is_some(),
+                    blocker_data.3.is_blocked || blocker_data.4.is_blocked,
+                ) {
+                    // Inputs of a gate don't block it directly.
+                    (true, true) => Color::ORANGE,
+                    (true, false) => Color::CYAN,
+                    (false, true) => Color::RED,
+                    (false, false) => Color::GREEN,
+                },
+            );
+            recurse_draw_relations(lines, q_blockers, *new_blocker);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spawn_node(
+        world: &mut World,
+        is_self_blocked: bool,
+        blockers: Vec<Entity>,
+        gate: Option<GateKind>,
+    ) -> Entity {
+        let mut node = world.spawn((
+            BaseNode,
+            Blockers { entities: blockers },
+            InheritedBlockStatus { is_blocked: false },
+            SelfBlockStatus {
+                is_blocked: is_self_blocked,
+            },
+        ));
+        if let Some(kind) = gate {
+            node.insert(NodeGate { kind });
+        }
+        node.id()
+    }
+
+    fn update_block_status(world: &mut World) {
+        let mut schedule = Schedule::new();
+        schedule.add_system(update_inherited_block_status);
+        schedule.run(world);
+    }
+
+    fn is_blocked(world: &World, e: Entity) -> bool {
+        world.get::<InheritedBlockStatus>(e).unwrap().is_blocked
+    }
+
+    #[test]
+    fn blocking_propagates_through_nodes() {
+        let mut world = World::new();
+        let blocker = spawn_node(&mut world, true, vec![], None);
+        let node = spawn_node(&mut world, false, vec![blocker], None);
+        let child = spawn_node(&mut world, false, vec![node], None);
+        let free = spawn_node(&mut world, false, vec![], None);
+        update_block_status(&mut world);
+        assert!(!is_blocked(&world, blocker));
+        assert!(is_blocked(&world, node));
+        assert!(is_blocked(&world, child));
+        assert!(!is_blocked(&world, free));
+    }
+
+    #[test]
+    fn gates_combine_their_inputs() {
+        for (kind, expected) in [
+            (GateKind::And, false),
+            (GateKind::Or, true),
+            (GateKind::Not, false),
+            (GateKind::Xor, true),
+        ] {
+            let mut world = World::new();
+            let blocking = spawn_node(&mut world, true, vec![], None);
+            let open = spawn_node(&mut world, false, vec![], None);
+            let gate = spawn_node(&mut world, false, vec![blocking, open], Some(kind));
+            let node = spawn_node(&mut world, false, vec![gate], None);
+            update_block_status(&mut world);
+            assert!(!is_blocked(&world, gate), "gates are never blocked");
+            assert_eq!(is_blocked(&world, node), expected, "{:?}", kind);
+            assert_eq!(
+                world.get::<SelfBlockStatus>(gate).unwrap().is_blocked,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn gate_without_inputs_never_blocks() {
+        for kind in [GateKind::And, GateKind::Or, GateKind::Not, GateKind::Xor] {
+            let mut world = World::new();
+            let gate = spawn_node(&mut world, true, vec![], Some(kind));
+            let node = spawn_node(&mut world, false, vec![gate], None);
+            update_block_status(&mut world);
+            assert!(!is_blocked(&world, node), "{:?}", kind);
+        }
+    }
+
+    #[test]
+    fn cycles_terminate() {
+        let mut world = World::new();
+        let a = spawn_node(&mut world, false, vec![], None);
+        let b = spawn_node(&mut world, false, vec![a], None);
+        world.get_mut::<Blockers>(a).unwrap().entities.push(b);
+        // A NOT gate fed by the node it blocks.
+        let gate = spawn_node(&mut world, false, vec![], Some(GateKind::Not));
+        let node = spawn_node(&mut world, false, vec![gate], None);
+        world.get_mut::<Blockers>(gate).unwrap().entities.push(node);
+        update_block_status(&mut world);
+        assert!(!is_blocked(&world, a));
+        assert!(!is_blocked(&world, b));
+        // Whatever the gate settles on, the node follows it.
+        assert_eq!(
+            is_blocked(&world, node),
+            world.get::<SelfBlockStatus>(gate).unwrap().is_blocked
+        );
+    }
+
+    #[test]
+    fn despawned_blockers_are_skipped() {
+        let mut world = World::new();
+        let blocker = spawn_node(&mut world, true, vec![], None);
+        let node = spawn_node(&mut world, false, vec![blocker], None);
+        let gate = spawn_node(&mut world, false, vec![blocker], Some(GateKind::Not));
+        world.despawn(blocker);
+        update_block_status(&mut world);
+        assert!(!is_blocked(&world, node));
+        assert!(world.get::<SelfBlockStatus>(gate).unwrap().is_blocked);
+    }
+}
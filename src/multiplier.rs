@@ -0,0 +1,97 @@
+use std::ops::DerefMut;
+
+use bevy::prelude::*;
+use bevy_prototype_debug_lines::DebugLines;
+
+use crate::balance::Balance;
+use crate::big_number::BigNumber;
+use crate::progress::{NodeTimer, Progress};
+use crate::upgrade_shop::NodeUpgrades;
+use crate::{InheritedBlockStatus, NodeCurrencyGain};
+
+/// Gains closer than this to a multiplier are boosted by it.
+pub const MULTIPLIER_RADIUS: f32 = 300f32;
+
+pub struct MultiplierPlugin;
+
+impl Plugin for MultiplierPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(draw_multiplier_reach);
+    }
+}
+
+/// Charged when its timer is finished, the next gain clicked in reach spends the charge.
+#[derive(Component)]
+pub struct NodeMultiplier {
+    pub level: u32,
+}
+
+impl NodeMultiplier {
+    /// Multiplies what a boosted gain produces.
+    pub fn yield_factor(&self) -> BigNumber {
+        BigNumber::from(1 + self.level)
+    }
+
+    pub fn label(&self) -> String {
+        format!("x{}", 1 + self.level)
+    }
+}
+
+pub fn is_in_reach(multiplier_pos: Vec2, gain_pos: Vec2) -> bool {
+    multiplier_pos.distance_squared(gain_pos) <= MULTIPLIER_RADIUS * MULTIPLIER_RADIUS
+}
+
+pub type MultiplierQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        &'static Transform,
+        &'static mut Progress,
+        &'static NodeMultiplier,
+        &'static NodeUpgrades,
+        &'static InheritedBlockStatus,
+    ),
+    Without<NodeCurrencyGain>,
+>;
+
+/// Spends the charge of unblocked multipliers reaching a gain clicked at `gain_pos`.
+///
+/// `multipliers` are their position, behaviour, upgrades duration factor,
+/// whether they are blocked, and timer. Returns the product of their factors.
+///
+/// Timers are only borrowed mutably when restarted,
+/// so a [`Mut`] timer is only marked changed when its multiplier is spent.
+pub fn consume_multipliers<T: NodeTimer, M: DerefMut<Target = T>>(
+    gain_pos: Vec2,
+    multipliers: impl Iterator<Item = (Vec2, NodeMultiplier, f32, bool, M)>,
+    balance: &Balance,
+) -> BigNumber {
+    let mut factor = BigNumber::ONE;
+    for (pos, multiplier, duration_factor, is_blocked, mut timer) in multipliers {
+        if is_blocked || !timer.is_finished() || !is_in_reach(pos, gain_pos) {
+            continue;
+        }
+        factor = factor * multiplier.yield_factor();
+        timer.restart(balance.multiplier_duration(multiplier.level) * duration_factor);
+    }
+    factor
+}
+
+/// Links charged multipliers to the gains they would boost.
+fn draw_multiplier_reach(
+    mut lines: ResMut<DebugLines>,
+    q_multipliers: Query<(&Transform, &Progress, &InheritedBlockStatus), With<NodeMultiplier>>,
+    q_gains: Query<&Transform, With<NodeCurrencyGain>>,
+) {
+    for (transform, progress, status) in q_multipliers.iter() {
+        if status.is_blocked || !progress.timer.finished() {
+            continue;
+        }
+        let pos = transform.translation.truncate();
+        for gain in q_gains.iter() {
+            if is_in_reach(pos, gain.translation.truncate()) {
+                lines.line_colored(transform.translation, gain.translation, 0f32, Color::GOLD);
+            }
+        }
+    }
+}
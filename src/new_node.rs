@@ -1,26 +1,90 @@
+use std::time::Duration;
+
+use bevy::ecs::system::EntityCommands;
 use bevy::prelude::*;
 use bevy::sprite::{MaterialMesh2dBundle, Mesh2dHandle};
 use bevy_easings::{Ease, EaseMethod, EasingType};
 use bevy_mod_picking::PickableBundle;
-use rand::distributions::WeightedIndex;
-use rand::prelude::Distribution;
-use rand::thread_rng;
+use rand::{thread_rng, Rng, SeedableRng};
 use rand_chacha::ChaCha20Rng;
 
-use crate::persisted_game::NodeType;
-use crate::{picking::AutoClick, picking::HighlightingMaterials, picking_aabb::HalfExtents, *};
+use crate::automation::{AutoClicker, NodeRule};
+use crate::balance::Balance;
+use crate::bank::NodeBank;
+use crate::big_number::BigNumber;
+use crate::converter::NodeConverter;
+use crate::gate::NodeGate;
+use crate::multiplier::NodeMultiplier;
+use crate::overclock::NodeOverclock;
+use crate::persisted_game::{NodeType, SavedRng};
+use crate::poisson::Poisson;
+use crate::prestige::Prestige;
+use crate::spawn_table::{SpawnContext, SpawnNode, SpawnTable};
+use crate::upgrade_shop::NodeUpgrades;
+use crate::{picking::HighlightingMaterials, picking_aabb::HalfExtents, *};
+
+/// Timers grow with currencies, which can get way bigger than a `Duration` can hold.
+pub const TIMER_MAX_SECONDS: f32 = 1e9f32;
+
+/// `currencies * mult` seconds, clamped to [`TIMER_MAX_SECONDS`].
+pub fn timer_for_currencies(currencies: BigNumber, mult: f32) -> f32 {
+    (currencies * mult as f64)
+        .to_f32()
+        .clamp(0f32, TIMER_MAX_SECONDS)
+}
 
-pub const TIMER_BLOCKER_MULT: f32 = 0.5f32; // / 10000f32;
-pub const TIMER_RESET_BLOCKER_FIXED: f32 = 0.5f32; // / 1000f32;
-pub const TIMER_GAIN_MULT: f32 = 0.3f32; // / 100000f32;
-pub const TIMER_GAIN_MULT_PER_LEVEL: f32 = 2f32; // / 10000f32;
-pub const TIMER_SAVE_BASE: f32 = 5f32; // / 10000f32;
-pub const TIMER_SAVE_MULT_PER_LEVEL: f32 = 5f32; // / 10000f32;
-pub const TIMER_SAVE_ADD_MULT_PER_CURRENCY: f32 = 0.5f32; // / 10000f32;
+/// Timer of a node spawned by a click owning `currencies_on_click`.
+pub fn spawned_node_duration(
+    node_type: &NodeType,
+    currencies_on_click: BigNumber,
+    balance: &Balance,
+    prestige: &Prestige,
+) -> f32 {
+    match node_type {
+        NodeType::Blocker { .. } => balance.blocker_duration(currencies_on_click),
+        // Spawned saves start as fast as a level 0 save.
+        NodeType::Save { .. } => balance.save_duration(0, currencies_on_click),
+        NodeType::Gain { level, .. } => {
+            balance.gain_duration(currencies_on_click, *level) * prestige.timer_factor()
+        }
+        NodeType::Multiplier { level } => balance.multiplier_duration(*level),
+        NodeType::Converter { .. } => balance.timer_converter,
+        NodeType::Gate { .. } => balance.timer_gate,
+        NodeType::Bank { .. } => balance.timer_bank,
+        NodeType::Overclock { .. } => balance.timer_overclock,
+    }
+}
+
+/// Where the node spawning next to `near` goes, what it is and its timer, drawn from `random`.
+///
+/// `None` when there is no room, or the spawn table picks no node.
+pub fn pick_new_node(
+    existing_points: &[(f32, f32)],
+    near: Vec2,
+    context: &SpawnContext,
+    spawn_table: &SpawnTable,
+    random: &mut ChaCha20Rng,
+    balance: &Balance,
+    prestige: &Prestige,
+) -> Option<(Vec2, NodeType, f32)> {
+    let pos = Poisson::new().compute_new_position(
+        existing_points,
+        &(near.x, near.y),
+        200f32,
+        10,
+        random,
+    )?;
+    let entry = spawn_table.pick(context, random)?;
+    let node_type = entry.node?.node_type(entry.initial_level);
+    let duration =
+        spawned_node_duration(&node_type, context.currencies_on_click, balance, prestige);
+    Some((Vec2::new(pos.0, pos.1), node_type, duration))
+}
 
 pub struct NewNodeEvent {
     pub entity: Entity,
-    pub currencies_on_click: i32,
+    /// Coins owned after the click.
+    pub currencies_on_click: BigNumber,
 }
 
 #[derive(Component)]
@@ -36,12 +100,30 @@ pub struct RandomForMap {
 
 impl Default for RandomForMap {
     fn default() -> Self {
-        let seed = thread_rng().gen::<u64>();
+        Self::from_seed(thread_rng().gen::<u64>())
+    }
+}
+
+impl RandomForMap {
+    pub fn from_seed(seed: u64) -> Self {
         Self {
             random: ChaCha20Rng::seed_from_u64(seed),
             seed,
         }
     }
+
+    pub fn from_saved(saved: &SavedRng) -> Self {
+        let mut map = Self::from_seed(saved.seed);
+        map.random.set_word_pos(saved.word_pos as u128);
+        map
+    }
+
+    pub fn to_saved(&self) -> SavedRng {
+        SavedRng {
+            seed: self.seed,
+            word_pos: self.random.get_word_pos() as u64,
+        }
+    }
 }
 
 pub fn create_node(
@@ -64,7 +146,6 @@ pub fn create_node(
             elapsed_time,
         ))
         .id();
-    commands.entity(eye_catcher).insert(AutoClick(ent));
     commands.spawn((
         MaterialMesh2dBundle {
             mesh: map_assets.mesh_gain.clone(),
@@ -108,7 +189,6 @@ pub fn insert_node(
             elapsed_time,
         ))
         .id();
-    commands.entity(eye_catcher).insert(AutoClick(ent));
     commands.spawn((
         MaterialMesh2dBundle {
             mesh: map_assets.mesh_gain.clone(),
@@ -150,6 +230,9 @@ fn bundle_node(
     ToBlock,
     HalfExtents,
     Highlight<ColorMaterial>,
+    NodeUpgrades,
+    NodeRule,
+    AutoClicker,
 ) {
     (
         MaterialMesh2dBundle {
@@ -176,6 +259,9 @@ fn bundle_node(
         ToBlock { entities: vec![] },
         HalfExtents(Vec2::splat(128f32 / 2f32)),
         highlights.node_materials_normal.clone(),
+        NodeUpgrades::default(),
+        NodeRule::default(),
+        AutoClicker::default(),
     )
 }
 
@@ -215,151 +301,186 @@ fn bundle_eye_catcher(
     )
 }
 
+/// Mesh and label of a node, the label shows once its timer is finished.
+pub fn node_visuals(node_type: &NodeType, map_assets: &MapAssets) -> (Mesh2dHandle, String) {
+    match *node_type {
+        // Blockers show whether they block instead.
+        NodeType::Blocker { .. } => (map_assets.mesh_blocker.clone(), String::new()),
+        NodeType::Save { .. } => (map_assets.mesh_save.clone(), "Save".to_string()),
+        NodeType::Gain { resource, .. } => (
+            map_assets.mesh_gain.clone(),
+            resource.gain_text().to_string(),
+        ),
+        NodeType::Multiplier { level } => (
+            map_assets.mesh_multiplier.clone(),
+            NodeMultiplier { level }.label(),
+        ),
+        // Filled by `update_converter_label`.
+        NodeType::Converter { .. } => (map_assets.mesh_converter.clone(), String::new()),
+        NodeType::Gate { kind } => (map_assets.mesh_gate.clone(), kind.name().to_string()),
+        NodeType::Bank { .. } => (map_assets.mesh_bank.clone(), "Deposit".to_string()),
+        NodeType::Overclock { level, scope } => (
+            map_assets.mesh_overclock.clone(),
+            NodeOverclock { level, scope }.label().to_string(),
+        ),
+    }
+}
+
+/// Inserts the behaviour components of a node, when spawned or loaded.
+///
+/// Links to other nodes are left to the caller.
+pub fn insert_behaviour(node: &mut EntityCommands, node_type: &NodeType, label: String) {
+    node.insert(NodeTextValidate { text: label });
+    match *node_type {
+        NodeType::Blocker { is_blocked } => {
+            // Until `check_self_block` looks at its timer.
+            node.insert((
+                NodeManualBlockToggle { is_blocked },
+                SelfBlockStatus { is_blocked: true },
+            ));
+        }
+        NodeType::Save { level } => {
+            node.insert(NodeSave { level });
+        }
+        NodeType::Gain { level, resource } => {
+            node.insert(NodeCurrencyGain { level, resource });
+        }
+        NodeType::Multiplier { level } => {
+            node.insert(NodeMultiplier { level });
+        }
+        NodeType::Converter { level, output } => {
+            node.insert(NodeConverter { level, output });
+        }
+        NodeType::Gate { kind } => {
+            node.insert(NodeGate { kind });
+        }
+        NodeType::Bank {
+            level,
+            deposit,
+            rate,
+            cycles,
+        } => {
+            node.insert(NodeBank {
+                level,
+                deposit,
+                rate,
+                cycles,
+            });
+        }
+        NodeType::Overclock { level, scope } => {
+            node.insert(NodeOverclock { level, scope });
+        }
+    }
+}
+
 pub fn new_button(
     mut commands: Commands,
     map_assets: Res<MapAssets>,
     highlights: Res<HighlightingMaterials>,
     mut random_map: ResMut<RandomForMap>,
     mut events: EventReader<NewNodeEvent>,
-    q_nodes: Query<(&Transform, Entity), With<BaseNode>>,
+    q_nodes: Query<(&Transform, Entity, NodeTypeQuery), With<BaseNode>>,
     mut q_blockers: Query<(&mut Blockers, Entity), With<BaseNode>>,
+    mut q_to_block: Query<&mut ToBlock, With<BaseNode>>,
+    prestige: Res<Prestige>,
+    balance: Res<Balance>,
+    spawn_table: Res<SpawnTable>,
 ) {
     if events.is_empty() {
         return;
     }
     let positions: Vec<_> = q_nodes
         .iter()
-        .map(|(t, e)| {
+        .map(|(t, e, _)| {
             let pos = t.translation.truncate();
             ((pos.x, pos.y), e)
         })
         .collect();
     let mut existing_points: Vec<_> = positions.into_iter().map(|(p, _)| p).collect();
+    let mut existing_nodes: Vec<_> = q_nodes
+        .iter()
+        .map(|(_, _, node_type)| SpawnNode::of(&node_type.node_type()))
+        .collect();
 
     for event in events.iter() {
-        let poisson = Poisson::new();
         let entity_from = event.entity;
-        let pos = q_nodes
-            .get(entity_from)
-            .expect("entity in event gain currency should be valid")
-            .0
-            .translation
-            .truncate();
-        match poisson.compute_new_position(
+        let Ok((transform, ..)) = q_nodes.get(entity_from) else {
+            continue;
+        };
+        let context = SpawnContext {
+            currencies_on_click: event.currencies_on_click,
+            prestige_points: prestige.points,
+            nodes: &existing_nodes,
+            source_blockers: q_blockers
+                .get(entity_from)
+                .map_or(0, |(blockers, _)| blockers.entities.len()),
+        };
+        let Some((pos, node_type, duration)) = pick_new_node(
             &existing_points,
-            &(pos.x, pos.y),
-            200f32,
-            10,
+            transform.translation.truncate(),
+            &context,
+            &spawn_table,
             &mut random_map.random,
-        ) {
-            None => {}
-            Some(pos) => {
-                let choices = [
-                    None,
-                    Some(NodeType::Blocker { is_blocked: true }),
-                    Some(NodeType::Save { level: 1 }),
-                    Some(NodeType::Gain { level: 1 }),
-                ];
-                let weights = [
-                    if event.currencies_on_click <= 1 {
-                        0
-                    } else {
-                        30
-                    },
-                    50,
-                    5,
-                    20,
-                ];
-                let dist = WeightedIndex::new(&weights).unwrap();
-                match choices[dist.sample(&mut random_map.random)] {
-                    Some(NodeType::Blocker { is_blocked }) => {
-                        create_blocker(
-                            &mut commands,
-                            &map_assets,
-                            &highlights,
-                            Vec2::new(pos.0, pos.1),
-                            event.currencies_on_click as f32 * TIMER_BLOCKER_MULT,
-                            &mut q_blockers,
-                            entity_from,
-                            is_blocked,
-                        );
-                        existing_points.push(pos);
-                    }
-                    Some(NodeType::Save { level }) => {
-                        let node = create_node(
-                            &mut commands,
-                            map_assets.mesh_save.clone(),
-                            &map_assets,
-                            &highlights,
-                            Vec2::new(pos.0, pos.1),
-                            TIMER_SAVE_BASE
-                                + (event.currencies_on_click as f32
-                                    * TIMER_SAVE_ADD_MULT_PER_CURRENCY),
-                            0f32,
-                        );
-                        commands.entity(node).insert(NodeTextValidate {
-                            text: "Save".to_string(),
+            &balance,
+            &prestige,
+        ) else {
+            continue;
+        };
+        existing_nodes.push(SpawnNode::of(&node_type));
+        let (mesh, label) = node_visuals(&node_type, &map_assets);
+        let node = create_node(
+            &mut commands,
+            mesh,
+            &map_assets,
+            &highlights,
+            pos,
+            duration,
+            0f32,
+        );
+        insert_behaviour(&mut commands.entity(node), &node_type, label);
+        match node_type {
+            NodeType::Blocker { .. } => {
+                if let Ok(mut blockers) = q_blockers.get_mut(entity_from) {
+                    blockers.0.entities.push(node);
+                } else {
+                    commands.entity(entity_from).insert(Blockers {
+                        entities: vec![node],
+                    });
+                }
+                commands.entity(node).insert(ToBlock {
+                    entities: vec![entity_from],
+                });
+            }
+            NodeType::Gate { .. } => {
+                // The blockers of the node it spawned from become its inputs.
+                let inputs = match q_blockers.get_mut(entity_from) {
+                    Ok(mut blockers) => std::mem::replace(&mut blockers.0.entities, vec![node]),
+                    Err(_) => {
+                        commands.entity(entity_from).insert(Blockers {
+                            entities: vec![node],
                         });
-                        commands.entity(node).insert(NodeSave { level });
-                        existing_points.push(pos);
+                        vec![]
                     }
-                    Some(NodeType::Gain { level }) => {
-                        let node = create_node(
-                            &mut commands,
-                            map_assets.mesh_gain.clone(),
-                            &map_assets,
-                            &highlights,
-                            Vec2::new(pos.0, pos.1),
-                            event.currencies_on_click as f32 * TIMER_GAIN_MULT
-                                + TIMER_GAIN_MULT_PER_LEVEL,
-                            0f32,
-                        );
-
-                        commands.entity(node).insert(NodeTextValidate {
-                            text: "Gain!".to_string(),
-                        });
-                        commands.entity(node).insert(NodeCurrencyGain { level });
-                        existing_points.push(pos);
+                };
+                for input in inputs.iter() {
+                    let Ok(mut to_block) = q_to_block.get_mut(*input) else {
+                        continue;
+                    };
+                    for target in to_block.entities.iter_mut() {
+                        if *target == entity_from {
+                            *target = node;
+                        }
                     }
-                    None => {}
                 }
+                commands
+                    .entity(node)
+                    .insert(Blockers { entities: inputs })
+                    .insert(ToBlock {
+                        entities: vec![entity_from],
+                    });
             }
+            _ => {}
         }
+        existing_points.push((pos.x, pos.y));
     }
 }
-
-fn create_blocker(
-    commands: &mut Commands,
-    map_assets: &Res<MapAssets>,
-    highlights: &Res<HighlightingMaterials>,
-    pos: Vec2,
-    duration: f32,
-    q_blockers: &mut Query<(&mut Blockers, Entity), With<BaseNode>>,
-    entity_from: Entity,
-    is_blocked: bool,
-) -> Entity {
-    let node = create_node(
-        commands,
-        map_assets.mesh_blocker.clone(),
-        map_assets,
-        highlights,
-        pos,
-        duration,
-        0f32,
-    );
-    commands
-        .entity(node)
-        .insert(NodeManualBlockToggle { is_blocked })
-        .insert(SelfBlockStatus { is_blocked });
-    if let Ok(mut blockers) = q_blockers.get_mut(entity_from) {
-        blockers.0.entities.push(node);
-    } else {
-        commands.entity(entity_from).insert(Blockers {
-            entities: vec![node],
-        });
-    }
-    commands.entity(node).insert(ToBlock {
-        entities: vec![entity_from],
-    });
-    node
-}
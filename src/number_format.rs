@@ -0,0 +1,168 @@
+use bevy::{input::common_conditions::input_just_pressed, prelude::*};
+use bevy_pkv::PkvStore;
+use serde::{Deserialize, Serialize};
+
+use crate::big_number::BigNumber;
+use crate::save_slots::is_typing;
+use crate::settings::Settings;
+
+/// Suffixes for each power of 1000, bigger numbers fall back to scientific notation.
+const SUFFIXES: [&str; 12] = [
+    "", "K", "M", "B", "T", "Qa", "Qi", "Sx", "Sp", "Oc", "No", "Dc",
+];
+
+/// Significant digits shown once a number doesn't fit in 3 digits.
+const SIGNIFICANT_DIGITS: i32 = 3;
+
+/// Normalized mantissas can be a few ulps off, like 999.5 stored as 999.4999999999999:
+/// nudging them away from zero before rounding keeps halves rounding up.
+const ROUNDING_NUDGE: f64 = 1f64 + 1e-12;
+
+pub struct NumberFormatPlugin;
+
+impl Plugin for NumberFormatPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(
+            cycle_notation
+                .run_if(input_just_pressed(KeyCode::N))
+                .run_if(not(is_typing)),
+        );
+    }
+}
+
+/// How numbers too big to be read at a glance are displayed.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Notation {
+    /// 1.23M
+    #[default]
+    Suffix,
+    /// 1.23e6
+    Scientific,
+    /// 1.23e6, with exponents multiple of 3 like 12.3e3
+    Engineering,
+}
+
+impl Notation {
+    pub fn next(self) -> Notation {
+        match self {
+            Notation::Suffix => Notation::Scientific,
+            Notation::Scientific => Notation::Engineering,
+            Notation::Engineering => Notation::Suffix,
+        }
+    }
+}
+
+fn cycle_notation(mut settings: ResMut<Settings>, mut pkv: ResMut<PkvStore>) {
+    settings.notation = settings.notation.next();
+    info!("number notation: {:?}", settings.notation);
+    settings.persist(&mut pkv);
+}
+
+/// Formats `value` for display, rounded to an integer below 1000.
+pub fn format_number(value: BigNumber, notation: Notation) -> String {
+    let small = (value.to_f64() * ROUNDING_NUDGE).round();
+    if small.abs() < 1000f64 {
+        // Avoids "-0".
+        return format!("{}", small as i64);
+    }
+    let sign = if value.mantissa() < 0f64 { "-" } else { "" };
+    // Rounding first, so 9.999e5 displays as 1.00M rather than 1000K.
+    let precision = 10f64.powi(SIGNIFICANT_DIGITS - 1);
+    let mut mantissa = (value.mantissa().abs() * precision * ROUNDING_NUDGE).round() / precision;
+    let mut exponent = value.exponent();
+    if mantissa >= 10f64 {
+        mantissa /= 10f64;
+        exponent += 1;
+    }
+    let group = exponent.div_euclid(3);
+    let group_digits = exponent.rem_euclid(3);
+    let scaled = mantissa * 10f64.powi(group_digits as i32);
+    let decimals = (SIGNIFICANT_DIGITS - 1 - group_digits as i32).max(0) as usize;
+    match notation {
+        Notation::Suffix if (group as usize) < SUFFIXES.len() => {
+            format!(
+                "{}{:.*}{}",
+                sign, decimals, scaled, SUFFIXES[group as usize]
+            )
+        }
+        Notation::Engineering => {
+            format!("{}{:.*}e{}", sign, decimals, scaled, group * 3)
+        }
+        Notation::Suffix | Notation::Scientific => {
+            let decimals = (SIGNIFICANT_DIGITS - 1) as usize;
+            format!("{}{:.*}e{}", sign, decimals, mantissa, exponent)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format(value: f64, notation: Notation) -> String {
+        format_number(BigNumber::from(value), notation)
+    }
+
+    #[test]
+    fn small_numbers_are_rounded_integers() {
+        assert_eq!(format(0f64, Notation::Suffix), "0");
+        assert_eq!(format(-0.4, Notation::Suffix), "0");
+        assert_eq!(format(12.6, Notation::Scientific), "13");
+        assert_eq!(format(999.4, Notation::Suffix), "999");
+        assert_eq!(format(-999f64, Notation::Engineering), "-999");
+    }
+
+    #[test]
+    fn negative_numbers_keep_their_sign() {
+        assert_eq!(format(-1234f64, Notation::Suffix), "-1.23K");
+        assert_eq!(format(-1234f64, Notation::Scientific), "-1.23e3");
+        assert_eq!(format(-12345f64, Notation::Engineering), "-12.3e3");
+    }
+
+    #[test]
+    fn rounding_up_moves_to_the_next_suffix() {
+        assert_eq!(format(999.5, Notation::Suffix), "1.00K");
+        assert_eq!(format(999_999f64, Notation::Suffix), "1.00M");
+        assert_eq!(format(999_999f64, Notation::Scientific), "1.00e6");
+        assert_eq!(format(999_999f64, Notation::Engineering), "1.00e6");
+    }
+
+    #[test]
+    fn suffixes() {
+        assert_eq!(format(1234f64, Notation::Suffix), "1.23K");
+        assert_eq!(format(12_345_678f64, Notation::Suffix), "12.3M");
+        assert_eq!(format(123_456_789f64, Notation::Suffix), "123M");
+        assert_eq!(format(1e33, Notation::Suffix), "1.00Dc");
+    }
+
+    #[test]
+    fn past_the_last_suffix_falls_back_to_scientific() {
+        assert_eq!(format(999.9e33, Notation::Suffix), "1.00e36");
+        assert_eq!(format(1e36, Notation::Suffix), "1.00e36");
+        assert_eq!(
+            format_number(BigNumber::new(4.56, 1000), Notation::Suffix),
+            "4.56e1000"
+        );
+    }
+
+    #[test]
+    fn scientific() {
+        assert_eq!(format(1234f64, Notation::Scientific), "1.23e3");
+        assert_eq!(format(12_345_678f64, Notation::Scientific), "1.23e7");
+        assert_eq!(
+            format_number(BigNumber::new(4.56, 1000), Notation::Scientific),
+            "4.56e1000"
+        );
+    }
+
+    #[test]
+    fn engineering() {
+        assert_eq!(format(1234f64, Notation::Engineering), "1.23e3");
+        assert_eq!(format(12_345f64, Notation::Engineering), "12.3e3");
+        assert_eq!(format(123_456f64, Notation::Engineering), "123e3");
+        assert_eq!(
+            format_number(BigNumber::new(4.56, 1000), Notation::Engineering),
+            "45.6e999"
+        );
+    }
+}
@@ -0,0 +1,317 @@
+use bevy::prelude::*;
+
+use crate::balance::Balance;
+use crate::bank::advance_saved_bank;
+use crate::persisted_game::{NodeType, Save, SavedNode};
+use crate::progress::tick_rate;
+use crate::{block_emits, BlockingNode, NodeManualBlockToggle};
+
+/// Below this amount of time away, we don't bother the player with a summary.
+const MIN_SECONDS_AWAY_FOR_SUMMARY: f32 = 5f32;
+const SUMMARY_DISPLAY_SECONDS: f32 = 8f32;
+
+pub struct OfflineProgressPlugin;
+
+impl Plugin for OfflineProgressPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<OfflineProgress>()
+            .add_system(show_offline_summary)
+            .add_system(hide_offline_summary);
+    }
+}
+
+/// What happened to the map while the game was closed.
+#[derive(Debug, Default, Clone)]
+pub struct OfflineProgress {
+    pub elapsed_seconds: f32,
+    pub finished_gains: u32,
+    pub finished_saves: u32,
+    pub finished_blockers: u32,
+}
+
+impl OfflineProgress {
+    pub fn finished_count(&self) -> u32 {
+        self.finished_gains + self.finished_saves + self.finished_blockers
+    }
+}
+
+/// Advances all timers of `save` up to `now` (seconds since 2023),
+/// following the same rules as `update_progress_timer`,
+/// `update_progress_manual_auto_block` and `update_inherited_block_status`,
+/// with the time buffs of overclocks running out along.
+///
+/// New nodes are not spawned offline, only existing timers progress.
+/// Banks don't change blocking, their cycles are compounded within each step.
+pub fn fast_forward(save: &mut Save, now: f64, balance: &Balance) -> OfflineProgress {
+    let elapsed = (now - save.last_tick_time_since2023).max(0f64) as f32;
+    save.last_tick_time_since2023 = now;
+    let mut report = OfflineProgress {
+        elapsed_seconds: elapsed,
+        ..default()
+    };
+
+    let mut remaining = elapsed;
+    while remaining > 0f32 {
+        let inherited = compute_inherited_block_status(&save.nodes);
+        let rates: Vec<f32> = save
+            .nodes
+            .iter()
+            .zip(inherited.iter())
+            .map(|(node, inherited)| {
+                saved_tick_rate(node, *inherited, balance) * save.time_buffs.time_scale_at(node.pos)
+            })
+            .collect();
+        // Time until the next timer finishes, as that may change blocking status.
+        // Buffs ending change rates too.
+        let step = save
+            .nodes
+            .iter()
+            .zip(rates.iter())
+            .filter(|(node, rate)| {
+                **rate > 0f32
+                    && node.timer_seconds_left > 0f32
+                    && !matches!(node.node_type, NodeType::Bank { .. })
+            })
+            .map(|(node, rate)| node.timer_seconds_left / rate)
+            .chain(save.time_buffs.next_expiry())
+            .fold(remaining, f32::min);
+        for (node, rate) in save.nodes.iter_mut().zip(rates.iter()) {
+            if let NodeType::Bank { .. } = node.node_type {
+                advance_saved_bank(node, step * rate, balance);
+                continue;
+            }
+            if *rate <= 0f32 || node.timer_seconds_left <= 0f32 {
+                continue;
+            }
+            if node.timer_seconds_left / rate <= step {
+                node.timer_seconds_left = 0f32;
+                on_finished(node, &mut report);
+            } else {
+                node.timer_seconds_left -= step * rate;
+            }
+        }
+        save.time_buffs.tick(step);
+        remaining -= step;
+    }
+    report
+}
+
+fn on_finished(node: &mut SavedNode, report: &mut OfflineProgress) {
+    match node.node_type {
+        NodeType::Gain { .. } => report.finished_gains += 1,
+        NodeType::Save { .. } => report.finished_saves += 1,
+        // Charged multipliers wait for a gain click, nothing to report.
+        NodeType::Multiplier { .. } => {}
+        // Ready converters wait for a click.
+        NodeType::Converter { .. } => {}
+        // Gates only wait before they can be switched.
+        NodeType::Gate { .. } => {}
+        // Advanced by `advance_saved_bank`.
+        NodeType::Bank { .. } => {}
+        // Ready overclocks wait for a click.
+        NodeType::Overclock { .. } => {}
+        NodeType::Blocker { .. } => {
+            // Mirrors `update_progress_manual_auto_block`.
+            node.node_type = NodeType::Blocker { is_blocked: true };
+            report.finished_blockers += 1;
+        }
+    }
+}
+
+/// The toggle of a saved blocker, `None` for other nodes.
+pub fn manual_block(node: &SavedNode) -> Option<NodeManualBlockToggle> {
+    match node.node_type {
+        NodeType::Blocker { is_blocked } => Some(NodeManualBlockToggle { is_blocked }),
+        _ => None,
+    }
+}
+
+/// [`tick_rate`] of a saved node.
+pub fn saved_tick_rate(node: &SavedNode, inherited_blocked: bool, balance: &Balance) -> f32 {
+    tick_rate(
+        manual_block(node).map(|manual| manual.is_blocked),
+        inherited_blocked,
+        balance,
+    )
+}
+
+/// Whether each saved node is blocked, see [`block_emits`].
+pub fn compute_inherited_block_status(nodes: &[SavedNode]) -> Vec<bool> {
+    let blocking_node = |index: usize| {
+        let node = nodes.get(index)?;
+        Some(BlockingNode {
+            blockers: &node.blockers,
+            gate: match node.node_type {
+                NodeType::Gate { kind } => Some(kind),
+                _ => None,
+            },
+            is_self_blocked: manual_block(node).is_some_and(|manual| manual.is_self_blocked(node)),
+        })
+    };
+    let emits = block_emits(0..nodes.len(), blocking_node);
+    (0..nodes.len())
+        .map(|index| blocking_node(index).is_some_and(|node| node.is_inherited_blocked(&emits)))
+        .collect()
+}
+
+#[derive(Component)]
+struct OfflineSummaryText(Timer);
+
+fn show_offline_summary(
+    mut commands: Commands,
+    map_assets: Res<crate::MapAssets>,
+    mut events: EventReader<OfflineProgress>,
+) {
+    for report in events.iter() {
+        if report.elapsed_seconds < MIN_SECONDS_AWAY_FOR_SUMMARY || report.finished_count() == 0 {
+            continue;
+        }
+        let mut finished = Vec::new();
+        if report.finished_gains > 0 {
+            finished.push(format!("{} Gain ready", report.finished_gains));
+        }
+        if report.finished_saves > 0 {
+            finished.push(format!("{} Save ready", report.finished_saves));
+        }
+        if report.finished_blockers > 0 {
+            finished.push(format!("{} blockers finished", report.finished_blockers));
+        }
+        let style = TextStyle {
+            color: Color::WHITE,
+            ..map_assets.text_style.clone()
+        };
+        commands.spawn((
+            TextBundle::from_sections(vec![
+                TextSection::new(
+                    format!(
+                        "While you were away ({}):\n",
+                        format_duration(report.elapsed_seconds)
+                    ),
+                    style.clone(),
+                ),
+                TextSection::new(finished.join(", "), style),
+            ])
+            .with_text_alignment(TextAlignment::Center)
+            .with_style(Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    bottom: Val::Px(15.0),
+                    left: Val::Px(15.0),
+                    ..default()
+                },
+                ..default()
+            }),
+            OfflineSummaryText(Timer::from_seconds(
+                SUMMARY_DISPLAY_SECONDS,
+                TimerMode::Once,
+            )),
+        ));
+    }
+}
+
+fn hide_offline_summary(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut q_summary: Query<(Entity, &mut OfflineSummaryText)>,
+) {
+    for (e, mut summary) in q_summary.iter_mut() {
+        if summary.0.tick(time.delta()).finished() {
+            commands.entity(e).despawn();
+        }
+    }
+}
+
+pub fn format_duration(seconds: f32) -> String {
+    let seconds = seconds as u64;
+    let (days, hours, minutes, seconds) = (
+        seconds / 86400,
+        seconds / 3600 % 24,
+        seconds / 60 % 60,
+        seconds % 60,
+    );
+    if days > 0 {
+        format!("{}d {}h", days, hours)
+    } else if hours > 0 {
+        format!("{}h {}m", hours, minutes)
+    } else if minutes > 0 {
+        format!("{}m {}s", minutes, seconds)
+    } else {
+        format!("{}s", seconds)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::persisted_game::test_saves::{gain, node, save_with};
+
+    #[test]
+    fn gain_finishes_while_away() {
+        let balance = Balance::default();
+        let mut save = save_with(vec![gain(2f32, 1f32)]);
+        let now = save.last_tick_time_since2023 + 5f64;
+        let report = fast_forward(&mut save, now, &balance);
+        assert_eq!(save.nodes[0].timer_seconds_left, 0f32);
+        assert_eq!(save.last_tick_time_since2023, now);
+        assert_eq!(report.elapsed_seconds, 5f32);
+        assert_eq!(report.finished_gains, 1);
+    }
+
+    #[test]
+    fn finished_blocker_keeps_slowing_what_it_blocks() {
+        let balance = Balance::default();
+        let mut blocked_gain = gain(10f32, 10f32);
+        blocked_gain.blockers = vec![1];
+        let mut blocker = node(NodeType::Blocker { is_blocked: false }, 3f32, 1f32);
+        blocker.to_block = vec![0];
+        let mut save = save_with(vec![blocked_gain, blocker]);
+        let now = save.last_tick_time_since2023 + 11f64;
+        let report = fast_forward(&mut save, now, &balance);
+
+        assert!(matches!(
+            save.nodes[1].node_type,
+            NodeType::Blocker { is_blocked: true }
+        ));
+        assert_eq!(save.nodes[1].timer_seconds_left, 0f32);
+        assert_eq!(report.finished_blockers, 1);
+        // Slowed for the whole 11 seconds: before the blocker finished, then by its auto-block.
+        let expected = 10f32 - 11f32 / balance.blocked_timer_slowdown;
+        assert!((save.nodes[0].timer_seconds_left - expected).abs() < 1e-4);
+        assert_eq!(report.finished_gains, 0);
+    }
+
+    #[test]
+    fn no_time_elapsed_changes_nothing() {
+        let balance = Balance::default();
+        for offset in [0f64, -60f64] {
+            let mut save = save_with(vec![gain(2f32, 1f32)]);
+            let now = save.last_tick_time_since2023 + offset;
+            let report = fast_forward(&mut save, now, &balance);
+            assert_eq!(save.nodes[0].timer_seconds_left, 1f32);
+            assert_eq!(report.elapsed_seconds, 0f32);
+            assert_eq!(report.finished_count(), 0);
+        }
+    }
+
+    #[test]
+    fn counts_finished_nodes_by_type() {
+        let balance = Balance::default();
+        let mut save = save_with(vec![
+            gain(2f32, 1f32),
+            gain(2f32, 2f32),
+            gain(100f32, 100f32),
+            node(NodeType::Save { level: 1 }, 5f32, 3f32),
+            node(NodeType::Blocker { is_blocked: false }, 3f32, 2f32),
+            node(NodeType::Multiplier { level: 1 }, 2f32, 1f32),
+        ]);
+        let now = save.last_tick_time_since2023 + 10f64;
+        let report = fast_forward(&mut save, now, &balance);
+        assert_eq!(report.finished_gains, 2);
+        assert_eq!(report.finished_saves, 1);
+        assert_eq!(report.finished_blockers, 1);
+        // Multipliers finishing are not reported.
+        assert_eq!(save.nodes[5].timer_seconds_left, 0f32);
+        assert_eq!(report.finished_count(), 4);
+        assert!((save.nodes[2].timer_seconds_left - 90f32).abs() < 1e-4);
+    }
+}
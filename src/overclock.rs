@@ -0,0 +1,305 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::automation::{ActivateNode, NodeActivation};
+use crate::balance::Balance;
+use crate::progress::{NodeTimer, Progress};
+use crate::upgrade_shop::NodeUpgrades;
+use crate::InheritedBlockStatus;
+
+/// Nodes closer than this to a regional overclock are sped up by it.
+pub const OVERCLOCK_RADIUS: f32 = 400f32;
+
+pub struct OverclockPlugin;
+
+impl Plugin for OverclockPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TimeBuffs>()
+            .add_startup_system(setup_countdown)
+            .add_system(node_overclock_react.after(NodeActivation))
+            .add_system(tick_time_buffs)
+            .add_system(update_countdown.after(tick_time_buffs));
+    }
+}
+
+#[derive(Reflect, FromReflect, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OverclockScope {
+    /// Speeds up every timer.
+    Global,
+    /// Speeds up timers in [`OVERCLOCK_RADIUS`].
+    Regional,
+}
+
+/// Adds a [`TimeBuff`] when clicked ready.
+#[derive(Component)]
+pub struct NodeOverclock {
+    pub level: u32,
+    pub scope: OverclockScope,
+}
+
+impl NodeOverclock {
+    pub fn label(&self) -> &'static str {
+        match self.scope {
+            OverclockScope::Global => "Boost all",
+            OverclockScope::Regional => "Boost near",
+        }
+    }
+
+    /// The buff added by an overclock at `pos`.
+    pub fn buff(&self, pos: Vec2, balance: &Balance) -> TimeBuff {
+        let (center, base) = match self.scope {
+            OverclockScope::Global => (None, balance.overclock_global_factor),
+            OverclockScope::Regional => (Some(pos), balance.overclock_regional_factor),
+        };
+        // Saved levels may be above a lowered maximum.
+        let level = self.level.min(balance.overclock_max_level);
+        TimeBuff {
+            center,
+            factor: base + balance.overclock_factor_per_level * level as f32,
+            seconds_left: balance.overclock_seconds,
+        }
+    }
+
+    /// Each use adds a level, up to `overclock_max_level`.
+    pub fn level_up(&mut self, balance: &Balance) {
+        self.level = (self.level + 1).min(balance.overclock_max_level);
+    }
+
+    /// Adds its buff when clicked ready.
+    pub fn click(
+        &mut self,
+        pos: Vec2,
+        timer: &mut impl NodeTimer,
+        upgrades: &NodeUpgrades,
+        buffs: &mut TimeBuffs,
+        balance: &Balance,
+    ) {
+        buffs.add(self.buff(pos, balance));
+        timer.restart(balance.timer_overclock * upgrades.duration_factor());
+        self.level_up(balance);
+    }
+}
+
+/// Timers go `factor` times faster while it lasts.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TimeBuff {
+    /// Center of a regional buff, `None` for a global one.
+    pub center: Option<Vec2>,
+    pub factor: f32,
+    /// Counted in real time, buffs don't speed up themselves.
+    pub seconds_left: f32,
+}
+
+impl TimeBuff {
+    fn reaches(&self, pos: Vec2) -> bool {
+        match self.center {
+            None => true,
+            Some(center) => center.distance_squared(pos) <= OVERCLOCK_RADIUS * OVERCLOCK_RADIUS,
+        }
+    }
+}
+
+/// Active buffs, saved with the game.
+///
+/// Buffs from the same place don't stack: the strongest factor and the longest time are kept.
+/// Different buffs don't add up either, a timer only gets the strongest global buff,
+/// times the strongest regional buff reaching it.
+#[derive(Resource, Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(transparent)]
+pub struct TimeBuffs {
+    pub buffs: Vec<TimeBuff>,
+}
+
+impl TimeBuffs {
+    pub fn add(&mut self, buff: TimeBuff) {
+        match self.buffs.iter_mut().find(|b| b.center == buff.center) {
+            Some(existing) => {
+                existing.factor = existing.factor.max(buff.factor);
+                existing.seconds_left = existing.seconds_left.max(buff.seconds_left);
+            }
+            None => self.buffs.push(buff),
+        }
+    }
+
+    /// How much faster timers of nodes at `pos` go.
+    pub fn time_scale_at(&self, pos: Vec2) -> f32 {
+        let strongest = |is_global: bool| {
+            self.buffs
+                .iter()
+                .filter(|buff| buff.center.is_none() == is_global && buff.reaches(pos))
+                .map(|buff| buff.factor)
+                .fold(1f32, f32::max)
+        };
+        strongest(true) * strongest(false)
+    }
+
+    /// Seconds until the next buff ends.
+    pub fn next_expiry(&self) -> Option<f32> {
+        self.buffs
+            .iter()
+            .map(|buff| buff.seconds_left)
+            .reduce(f32::min)
+    }
+
+    /// Counts down all buffs by `seconds`, removing ended ones.
+    pub fn tick(&mut self, seconds: f32) {
+        for buff in self.buffs.iter_mut() {
+            buff.seconds_left -= seconds;
+        }
+        self.buffs.retain(|buff| buff.seconds_left > 0f32);
+    }
+}
+
+fn node_overclock_react(
+    mut events: EventReader<ActivateNode>,
+    mut q_overclocks: Query<(
+        &Transform,
+        &mut Progress,
+        &mut NodeOverclock,
+        &NodeUpgrades,
+        &InheritedBlockStatus,
+    )>,
+    mut buffs: ResMut<TimeBuffs>,
+    balance: Res<Balance>,
+) {
+    for event in events.iter() {
+        let Ok((transform, mut p, mut overclock, upgrades, status)) = q_overclocks.get_mut(event.0)
+        else {
+            continue;
+        };
+        if status.is_blocked || !p.timer.finished() {
+            continue;
+        }
+        overclock.click(
+            transform.translation.truncate(),
+            &mut *p,
+            upgrades,
+            &mut buffs,
+            &balance,
+        );
+    }
+}
+
+fn tick_time_buffs(time: Res<Time>, mut buffs: ResMut<TimeBuffs>) {
+    // Keeps change detection quiet when no buff is active.
+    if !buffs.buffs.is_empty() {
+        buffs.tick(time.delta_seconds());
+    }
+}
+
+#[derive(Component)]
+struct CountdownText;
+
+fn setup_countdown(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                font_size: 20.0,
+                color: Color::CYAN,
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                top: Val::Px(5.0),
+                left: Val::Px(15.0),
+                ..default()
+            },
+            ..default()
+        }),
+        CountdownText,
+    ));
+}
+
+fn update_countdown(buffs: Res<TimeBuffs>, mut q_text: Query<&mut Text, With<CountdownText>>) {
+    if !buffs.is_changed() {
+        return;
+    }
+    let lines: Vec<String> = buffs
+        .buffs
+        .iter()
+        .map(|buff| {
+            format!(
+                "x{:.2} {}: {:.0}s",
+                buff.factor,
+                if buff.center.is_none() { "all" } else { "near" },
+                buff.seconds_left.ceil()
+            )
+        })
+        .collect();
+    for mut text in q_text.iter_mut() {
+        text.sections[0].value = lines.join("\n");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn buff(center: Option<Vec2>, factor: f32, seconds_left: f32) -> TimeBuff {
+        TimeBuff {
+            center,
+            factor,
+            seconds_left,
+        }
+    }
+
+    #[test]
+    fn buffs_from_the_same_place_keep_the_best_of_both() {
+        let mut buffs = TimeBuffs::default();
+        buffs.add(buff(None, 2f32, 5f32));
+        buffs.add(buff(None, 1.5, 20f32));
+        assert_eq!(buffs.buffs, vec![buff(None, 2f32, 20f32)]);
+        let center = Some(Vec2::new(10f32, 0f32));
+        buffs.add(buff(center, 3f32, 1f32));
+        buffs.add(buff(Some(Vec2::ZERO), 4f32, 1f32));
+        assert_eq!(buffs.buffs.len(), 3);
+    }
+
+    #[test]
+    fn time_scale_multiplies_the_strongest_global_and_regional_buffs() {
+        let mut buffs = TimeBuffs::default();
+        assert_eq!(buffs.time_scale_at(Vec2::ZERO), 1f32);
+        buffs.add(buff(None, 2f32, 10f32));
+        buffs.add(buff(Some(Vec2::ZERO), 3f32, 10f32));
+        buffs.add(buff(Some(Vec2::new(100f32, 0f32)), 1.5, 10f32));
+        assert_eq!(buffs.time_scale_at(Vec2::ZERO), 6f32);
+        // Only the global buff reaches that far.
+        let far = Vec2::new(OVERCLOCK_RADIUS * 3f32, 0f32);
+        assert_eq!(buffs.time_scale_at(far), 2f32);
+        let edge = Vec2::new(0f32, OVERCLOCK_RADIUS);
+        assert_eq!(buffs.time_scale_at(edge), 6f32);
+    }
+
+    #[test]
+    fn tick_removes_ended_buffs() {
+        let mut buffs = TimeBuffs::default();
+        buffs.add(buff(None, 2f32, 10f32));
+        buffs.add(buff(Some(Vec2::ZERO), 3f32, 4f32));
+        assert_eq!(buffs.next_expiry(), Some(4f32));
+        buffs.tick(4f32);
+        assert_eq!(buffs.buffs, vec![buff(None, 2f32, 6f32)]);
+        assert_eq!(buffs.time_scale_at(Vec2::ZERO), 2f32);
+        buffs.tick(10f32);
+        assert!(buffs.buffs.is_empty());
+        assert_eq!(buffs.next_expiry(), None);
+        assert_eq!(buffs.time_scale_at(Vec2::ZERO), 1f32);
+    }
+
+    #[test]
+    fn levels_are_capped() {
+        let balance = Balance::default();
+        let mut overclock = NodeOverclock {
+            level: balance.overclock_max_level - 1,
+            scope: OverclockScope::Global,
+        };
+        overclock.level_up(&balance);
+        overclock.level_up(&balance);
+        assert_eq!(overclock.level, balance.overclock_max_level);
+        let capped = overclock.buff(Vec2::ZERO, &balance).factor;
+        overclock.level = 1000;
+        assert_eq!(overclock.buff(Vec2::ZERO, &balance).factor, capped);
+    }
+}
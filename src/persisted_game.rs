@@ -1,42 +1,107 @@
-use bevy::{input::common_conditions::input_just_pressed, prelude::*, utils::HashMap};
-use bevy_pkv::PkvStore;
+use bevy::{
+    ecs::system::SystemParam,
+    input::common_conditions::input_just_pressed,
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
+use bevy_pkv::{GetError, PkvStore};
+use rand::{thread_rng, Rng};
 use serde::{Deserialize, Serialize};
 
 use crate::{
+    automation::NodeRule,
+    big_number::BigNumber,
+    converter::ConverterOutput,
     currency,
-    idle_gains::Currency,
-    new_node::{insert_node, BaseNode, EyeCatcher},
+    gate::GateKind,
+    idle_gains::{Ledger, ResourceKind},
+    income_stats::{IncomeHistory, IncomeStats},
+    new_node::{insert_behaviour, insert_node, node_visuals, BaseNode, EyeCatcher, RandomForMap},
+    overclock::{OverclockScope, TimeBuffs},
     picking::HighlightingMaterials,
-    progress::{NodeTextValidate, Progress},
-    Blockers, ButtonRef, MapAssets, NodeCurrencyGain, NodeManualBlockToggle, NodeSave,
-    SelfBlockStatus, ToBlock,
+    prestige::Prestige,
+    progress::Progress,
+    save_history::SaveHistory,
+    save_migration::{self, CURRENT_SAVE_VERSION},
+    save_slots::{is_typing, SaveSlots, SlotId},
+    save_validation::{self, SaveProblem, SaveProblems},
+    settings::Settings,
+    upgrade_shop::NodeUpgrades,
+    Blockers, ButtonRef, MapAssets, NodeTypeQuery, ToBlock,
 };
 
 pub struct GameLoader;
 
 impl Plugin for GameLoader {
     fn build(&self, app: &mut App) {
-        app.insert_resource(PkvStore::new("sidleffect", "save"));
+        let mut pkv = PkvStore::new("sidleffect", "save");
+        app.insert_resource(SaveSlots::load(&mut pkv));
+        app.insert_resource(Settings::load(&pkv));
+        app.insert_resource(pkv);
+        app.init_resource::<LastSave>();
+        app.init_resource::<MapSlot>();
+        app.init_resource::<ProtectedSlots>();
         app.register_type::<LoadingNode>();
         app.register_type::<LoadingPending>();
         app.add_event::<EventSave>();
+        app.add_event::<EventLoad>();
         app.add_system(load_system.in_base_set(CoreSet::PreUpdate));
         app.add_system(save_event);
-        app.add_system(save_cheat.run_if(input_just_pressed(KeyCode::S)));
+        app.add_system(
+            save_cheat
+                .run_if(input_just_pressed(KeyCode::S))
+                .run_if(not(is_typing)),
+        );
 
         app.add_system(
             clear
                 .in_base_set(CoreSet::PreUpdate)
-                .run_if(input_just_pressed(KeyCode::E)),
+                .run_if(input_just_pressed(KeyCode::E))
+                .run_if(not(is_typing)),
         );
     }
 }
 
 #[derive(Reflect, Serialize, Deserialize, Clone, Debug)]
 pub enum NodeType {
-    Gain { level: u32 },
-    Save { level: u32 },
-    Blocker { is_blocked: bool },
+    Gain {
+        level: u32,
+        /// Gain nodes only produced coins before resources existed.
+        #[serde(default)]
+        resource: ResourceKind,
+    },
+    Save {
+        level: u32,
+    },
+    Blocker {
+        is_blocked: bool,
+    },
+    /// Boosts gains around it, see [`NodeMultiplier`].
+    Multiplier {
+        level: u32,
+    },
+    /// Trades coins for a resource or an effect, see [`NodeConverter`].
+    Converter {
+        level: u32,
+        output: ConverterOutput,
+    },
+    /// Blocks from its inputs, see [`NodeGate`].
+    Gate {
+        kind: GateKind,
+    },
+    /// Grows a deposit of coins, see [`NodeBank`].
+    Bank {
+        level: u32,
+        deposit: BigNumber,
+        rate: f64,
+        #[serde(default)]
+        cycles: u32,
+    },
+    /// Speeds up timers for a while, see [`NodeOverclock`].
+    Overclock {
+        level: u32,
+        scope: OverclockScope,
+    },
 }
 
 #[derive(Reflect, Serialize, Deserialize, Clone, Debug)]
@@ -47,27 +112,89 @@ pub struct SavedNode {
     pub timer_seconds_left: f32,
     pub to_block: Vec<usize>,
     pub blockers: Vec<usize>,
+    #[serde(default)]
+    pub upgrades: NodeUpgrades,
+    #[serde(default)]
+    pub rule: NodeRule,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+/// State of [`RandomForMap`], so new nodes are the same after loading.
+#[derive(Reflect, Serialize, Deserialize, Clone, Debug)]
+pub struct SavedRng {
+    pub seed: u64,
+    /// Position in the ChaCha stream, in 32-bit words.
+    pub word_pos: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Save {
-    pub currencies: i32,
-    pub last_tick_time_since2023: f32,
+    /// See [`save_migration`] for older versions.
+    pub version: u32,
+    pub resources: Ledger,
+    /// Wall-clock seconds since 2023-01-01 when this save was made, see [`now_since2023`].
+    pub last_tick_time_since2023: f64,
+    pub map_rng: SavedRng,
     pub nodes: Vec<SavedNode>,
+    #[serde(default)]
+    pub income: IncomeHistory,
+    /// Meta progression, kept when prestige resets everything else.
+    #[serde(default)]
+    pub prestige: Prestige,
+    /// Buffs of overclocks still running.
+    #[serde(default)]
+    pub time_buffs: TimeBuffs,
+}
+
+/// 2023-01-01T00:00:00Z as a unix timestamp.
+const UNIX_TIMESTAMP_2023: f64 = 1_672_531_200f64;
+
+/// Current wall-clock time, in seconds since 2023-01-01.
+pub fn now_since2023() -> f64 {
+    instant::SystemTime::now()
+        .duration_since(instant::SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_secs_f64() - UNIX_TIMESTAMP_2023)
+        .unwrap_or(0f64)
+}
+
+/// Where the game was saved before save slots existed.
+pub const LEGACY_SAVE_KEY: &str = "game";
+
+pub fn save_key(slot: SlotId) -> String {
+    format!("slot_{}", slot)
+}
+
+/// Where an unreadable save is kept, so a failed load doesn't lose it.
+pub fn backup_key(slot: SlotId) -> String {
+    format!("slot_{}_backup", slot)
 }
 
-pub fn load(pkv: &Res<PkvStore>) -> Result<Save, String> {
-    let get_default_save = || Save {
-        currencies: 0,
-        last_tick_time_since2023: 2f32,
+pub fn default_save() -> Save {
+    default_save_with_seed(thread_rng().gen())
+}
+
+/// A new game, generating its map from `seed`.
+pub fn default_save_with_seed(seed: u64) -> Save {
+    Save {
+        version: CURRENT_SAVE_VERSION,
+        resources: Ledger::default(),
+        last_tick_time_since2023: now_since2023(),
+        map_rng: SavedRng { seed, word_pos: 0 },
+        income: IncomeHistory::default(),
+        prestige: Prestige::default(),
+        time_buffs: TimeBuffs::default(),
         nodes: vec![
             SavedNode {
                 pos: Vec2::default(),
-                node_type: NodeType::Gain { level: 1 },
+                node_type: NodeType::Gain {
+                    level: 1,
+                    resource: ResourceKind::Coins,
+                },
                 timer_seconds_duration: 2.0,
                 timer_seconds_left: 2.0,
                 to_block: vec![],
                 blockers: vec![1],
+                upgrades: NodeUpgrades::default(),
+                rule: NodeRule::default(),
             },
             SavedNode {
                 pos: Vec2::new(0f32, 230f32),
@@ -76,83 +203,285 @@ pub fn load(pkv: &Res<PkvStore>) -> Result<Save, String> {
                 timer_seconds_left: 1.0,
                 to_block: vec![0],
                 blockers: vec![],
+                upgrades: NodeUpgrades::default(),
+                rule: NodeRule::default(),
             },
         ],
+    }
+}
+
+#[derive(Debug)]
+pub enum LoadError {
+    /// The stored data is not a valid save, it couldn't be backed up.
+    Unreadable(String),
+    /// The save couldn't be migrated or read, it was copied to [`backup_key`].
+    BackedUp(String, SlotId),
+}
+
+impl std::fmt::Display for LoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LoadError::Unreadable(reason) => write!(f, "unreadable save: {}", reason),
+            LoadError::BackedUp(reason, slot) => write!(
+                f,
+                "could not load save, it was kept under \"{}\": {}",
+                backup_key(*slot),
+                reason
+            ),
+        }
+    }
+}
+
+/// Migrates then reads a save of any version.
+pub fn parse_save(raw: serde_json::Value) -> Result<Save, String> {
+    save_migration::migrate(raw)
+        .map_err(|err| err.to_string())
+        .and_then(|migrated| {
+            serde_json::from_value::<Save>(migrated).map_err(|err| err.to_string())
+        })
+}
+
+/// Reads the save of `slot`, migrating it to the current version if needed.
+///
+/// A missing or empty save gives the default map.
+pub fn load(pkv: &mut PkvStore, slot: SlotId) -> Result<Save, LoadError> {
+    let raw = match pkv.get::<serde_json::Value>(save_key(slot)) {
+        Ok(serde_json::Value::Null) => return Ok(default_save()),
+        Ok(raw) => raw,
+        Err(GetError::NotFound) => return Ok(default_save()),
+        Err(err) => return Err(LoadError::Unreadable(err.to_string())),
     };
-    let mut save = pkv
-        .get::<Save>("game")
-        .unwrap_or_else(|_err| get_default_save());
-    if save.nodes.is_empty() {
-        save = get_default_save();
+    read_or_back_up(raw, slot, |key, raw| {
+        pkv.set(key, raw).map_err(|err| err.to_string())
+    })
+}
+
+/// Reads the stored `raw` save of `slot`, giving it to `back_up` with its backup key if unreadable.
+fn read_or_back_up(
+    raw: serde_json::Value,
+    slot: SlotId,
+    back_up: impl FnOnce(String, &serde_json::Value) -> Result<(), String>,
+) -> Result<Save, LoadError> {
+    match parse_save(raw.clone()) {
+        Ok(save) if save.nodes.is_empty() => Ok(default_save()),
+        Ok(save) => Ok(save),
+        Err(reason) => {
+            if let Err(err) = back_up(backup_key(slot), &raw) {
+                return Err(LoadError::Unreadable(format!(
+                    "{}, and backup failed: {}",
+                    reason, err
+                )));
+            }
+            Err(LoadError::BackedUp(reason, slot))
+        }
     }
-    Ok(save)
 }
 
-pub struct EventSave;
+pub struct EventSave {
+    pub slot: SlotId,
+}
+
+/// Replaces the current map with the save of `slot`.
+pub struct EventLoad {
+    pub slot: SlotId,
+}
 
-pub fn save_cheat(mut evt: EventWriter<EventSave>) {
-    evt.send(EventSave);
+pub fn save_cheat(slots: Res<SaveSlots>, mut evt: EventWriter<EventSave>) {
+    evt.send(EventSave {
+        slot: slots.current,
+    });
 }
 
-pub fn save_event(
-    currencies: Res<Currency>,
+/// Everything needed to build a [`Save`] from the current map.
+#[derive(SystemParam)]
+pub struct SaveSnapshot<'w, 's> {
+    ledger: Res<'w, Ledger>,
+    income: Res<'w, IncomeStats>,
+    prestige: Res<'w, Prestige>,
+    time_buffs: Res<'w, TimeBuffs>,
+    random_map: Res<'w, RandomForMap>,
     q_nodes: Query<
+        'w,
+        's,
         (
             Entity,
-            &Transform,
-            &Progress,
-            Option<&NodeCurrencyGain>,
-            Option<&NodeSave>,
-            &SelfBlockStatus,
-            &ToBlock,
-            &Blockers,
+            &'static Transform,
+            &'static Progress,
+            NodeTypeQuery,
+            &'static ToBlock,
+            &'static Blockers,
+            &'static NodeUpgrades,
+            &'static NodeRule,
         ),
         With<BaseNode>,
     >,
-    mut pkv: ResMut<PkvStore>,
-    evt: EventReader<EventSave>,
-) {
-    if evt.is_empty() {
-        return;
+}
+
+impl<'w, 's> SaveSnapshot<'w, 's> {
+    /// References to entities which are not nodes anymore are dropped, and reported.
+    pub fn snapshot(&self) -> (Save, Vec<SaveProblem>) {
+        let mut node_entities_index: HashMap<Entity, usize> = HashMap::new();
+        for (i, (e, _, _, _, _, _, _, _)) in self.q_nodes.iter().enumerate() {
+            node_entities_index.insert(e, i);
+        }
+        let mut nodes = Vec::new();
+        let mut problems = Vec::new();
+        let mut to_index = |entities: &Vec<Entity>| -> Vec<usize> {
+            entities
+                .iter()
+                .filter_map(|entity| {
+                    let index = node_entities_index.get(entity).copied();
+                    if index.is_none() {
+                        problems.push(SaveProblem::DanglingEntity { entity: *entity });
+                    }
+                    index
+                })
+                .collect()
+        };
+
+        for (_e, transform, progress, node_type, to_block, blockers, upgrades, rule) in
+            self.q_nodes.iter()
+        {
+            nodes.push(SavedNode {
+                pos: transform.translation.truncate(),
+                node_type: node_type.node_type(),
+                timer_seconds_duration: progress.timer.duration().as_secs_f32(),
+                timer_seconds_left: progress.timer.remaining_secs(),
+                to_block: to_index(&to_block.entities),
+                blockers: to_index(&blockers.entities),
+                upgrades: upgrades.clone(),
+                rule: rule.clone(),
+            });
+        }
+        let mut save = Save {
+            version: CURRENT_SAVE_VERSION,
+            resources: self.ledger.clone(),
+            last_tick_time_since2023: now_since2023(),
+            map_rng: self.random_map.to_saved(),
+            nodes,
+            income: self.income.history.clone(),
+            prestige: self.prestige.clone(),
+            time_buffs: self.time_buffs.clone(),
+        };
+        problems.extend(save_validation::validate(&mut save));
+        (save, problems)
     }
-    let mut node_entities_index: HashMap<Entity, usize> = HashMap::new();
-    for (i, (e, _, _, _, _, _, _, _)) in q_nodes.iter().enumerate() {
-        node_entities_index.insert(e, i);
+}
+
+/// When the game was last written to storage.
+#[derive(Resource, Default)]
+pub struct LastSave {
+    pub time_since2023: Option<f64>,
+}
+
+#[derive(Debug)]
+pub enum SaveError {
+    /// A map is loading, or the map is not the one of the slot, see [`SaveWriter::save`].
+    Loading,
+    /// The map is loading or was cleared, overwriting the slot would lose it.
+    EmptyMap,
+    /// Storage refused the save, it was reported as a [`SaveProblem::NotWritten`].
+    Storage(String),
+    /// The slot holds a save which couldn't be read nor backed up, see [`ProtectedSlots`].
+    Protected,
+}
+
+impl std::fmt::Display for SaveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SaveError::Loading => write!(f, "a map is loading"),
+            SaveError::EmptyMap => write!(f, "map is empty"),
+            SaveError::Storage(reason) => write!(f, "storage failed: {}", reason),
+            SaveError::Protected => write!(f, "slot holds an unreadable save"),
+        }
     }
-    let mut nodes = Vec::new();
-
-    for (_e, transform, progress, gain, save, self_status, to_block, blockers) in q_nodes.iter() {
-        nodes.push(SavedNode {
-            pos: transform.translation.truncate(),
-            node_type: match (gain, save) {
-                (Some(g), _) => NodeType::Gain { level: g.level },
-                (_, Some(save)) => NodeType::Save { level: save.level },
-                (None, None) => NodeType::Blocker {
-                    is_blocked: self_status.is_blocked,
-                },
-            },
-            timer_seconds_duration: progress.timer.duration().as_secs_f32(),
-            timer_seconds_left: progress.timer.remaining_secs(),
-            to_block: to_block
-                .entities
-                .iter()
-                .map(|b| node_entities_index[b])
-                .collect(),
-            blockers: blockers
-                .entities
-                .iter()
-                .map(|b| node_entities_index[b])
-                .collect(),
-        });
+}
+
+/// Slot the spawned map was loaded from, set once its nodes are spawned.
+#[derive(Resource, Default)]
+pub struct MapSlot {
+    pub slot: Option<SlotId>,
+}
+
+/// Slots whose stored save couldn't be read nor backed up.
+///
+/// Their data is the only copy of the player's progress, it must not be saved over.
+#[derive(Resource, Default)]
+pub struct ProtectedSlots {
+    pub slots: HashSet<SlotId>,
+}
+
+/// Writes saves to storage, keeping track of problems and save time.
+#[derive(SystemParam)]
+pub struct SaveWriter<'w, 's> {
+    pkv: ResMut<'w, PkvStore>,
+    problems: ResMut<'w, SaveProblems>,
+    last_save: ResMut<'w, LastSave>,
+    map_slot: Res<'w, MapSlot>,
+    protected: Res<'w, ProtectedSlots>,
+    q_loading: Query<'w, 's, (), With<LoadingPending>>,
+}
+
+impl<'w, 's> SaveWriter<'w, 's> {
+    /// Saves the current map into `slot`, returns the written save.
+    ///
+    /// An empty map is not saved: the map is loading or was cleared,
+    /// overwriting the slot would lose it.
+    /// The map is not saved either while another one is loading,
+    /// or into a slot it wasn't loaded from, as the current slot changes before the map.
+    /// [`ProtectedSlots`] are never saved over.
+    ///
+    /// Storage errors are logged and shown with the other [`SaveProblems`],
+    /// a full or denied storage must not stop the game.
+    pub fn save(&mut self, snapshot: &SaveSnapshot, slot: SlotId) -> Result<Save, SaveError> {
+        if !self.q_loading.is_empty() || self.map_slot.slot != Some(slot) {
+            debug!("a map is loading, not saving slot {}", slot);
+            return Err(SaveError::Loading);
+        }
+        if self.protected.slots.contains(&slot) {
+            debug!("slot {} holds an unreadable save, not saving it", slot);
+            return Err(SaveError::Protected);
+        }
+        let (data, mut save_problems) = snapshot.snapshot();
+        if data.nodes.is_empty() {
+            self.problems.report("save", save_problems);
+            warn!("map is empty, not saving slot {}", slot);
+            return Err(SaveError::EmptyMap);
+        }
+        if let Err(err) = self.pkv.set(save_key(slot), &data) {
+            error!("could not save slot {}: {}", slot, err);
+            let reason = err.to_string();
+            save_problems.push(SaveProblem::NotWritten {
+                slot,
+                reason: reason.clone(),
+            });
+            self.problems.report("save", save_problems);
+            return Err(SaveError::Storage(reason));
+        }
+        self.problems.report("save", save_problems);
+        SaveHistory::push(&mut self.pkv, slot, &data);
+        self.last_save.time_since2023 = Some(data.last_tick_time_since2023);
+        Ok(data)
+    }
+
+    /// Storage the saves are written to, for systems also changing slots.
+    pub fn pkv(&mut self) -> &mut PkvStore {
+        &mut self.pkv
+    }
+
+    /// Problems shown to the player, for systems also reporting their own.
+    pub fn problems(&mut self) -> &mut SaveProblems {
+        &mut self.problems
+    }
+}
+
+pub fn save_event(snapshot: SaveSnapshot, mut writer: SaveWriter, mut evt: EventReader<EventSave>) {
+    for evt in evt.iter() {
+        // Failures are already reported by the writer.
+        if let Ok(data) = writer.save(&snapshot, evt.slot) {
+            debug!("saved slot {} with {} nodes", evt.slot, data.nodes.len());
+        }
     }
-    let data = Save {
-        currencies: currencies.amount,
-        last_tick_time_since2023: 2f32,
-        nodes,
-    };
-    pkv.set("game", &data).expect("pkv should be able to save.");
-    let j = serde_json::to_string(&data).unwrap();
-    println!("saved: {}", j);
 }
 
 #[derive(Reflect, Component)]
@@ -161,25 +490,47 @@ pub struct LoadingNode(pub SavedNode);
 /// To find LoadingNode indices corresponding entities
 #[derive(Reflect, Component)]
 pub struct LoadingPending {
-    pub currencies: i32,
+    /// Slot the loaded map will be saved to.
+    pub slot: SlotId,
+    #[reflect(ignore)]
+    pub resources: Ledger,
+    pub map_rng: SavedRng,
     pub nodes: Vec<Entity>,
+    #[reflect(ignore)]
+    pub income: IncomeHistory,
+    #[reflect(ignore)]
+    pub prestige: Prestige,
+    #[reflect(ignore)]
+    pub time_buffs: TimeBuffs,
 }
 
-pub fn start_load(commands: &mut Commands, save: &Save) {
+/// Validates `save` then prepares its loading as the map of `slot`,
+/// returns problems found in the save.
+pub fn start_load(commands: &mut Commands, save: &Save, slot: SlotId) -> Vec<SaveProblem> {
+    let mut save = save.clone();
+    let problems = save_validation::validate(&mut save);
     let mut node_entities_index: Vec<Entity> = Vec::new();
     for n in &save.nodes {
         node_entities_index.push(commands.spawn(LoadingNode((*n).clone())).id());
     }
     commands.spawn(LoadingPending {
-        currencies: save.currencies,
+        slot,
+        resources: save.resources.clone(),
+        map_rng: save.map_rng.clone(),
         nodes: node_entities_index,
+        income: save.income.clone(),
+        prestige: save.prestige.clone(),
+        time_buffs: save.time_buffs.clone(),
     });
+    problems
 }
 
-fn clear(
-    mut commands: Commands,
-    q_old_nodes: Query<(Entity, Option<&EyeCatcher>), Or<(With<ButtonRef>, With<BaseNode>)>>,
-) {
+/// Nodes and their button parts, with the eye catcher to remove along.
+pub type MapNodesQuery<'w, 's> =
+    Query<'w, 's, (Entity, Option<&'static EyeCatcher>), Or<(With<ButtonRef>, With<BaseNode>)>>;
+
+/// Removes every node of the map.
+pub fn clear_map(commands: &mut Commands, q_old_nodes: &MapNodesQuery) {
     for (e_old, eye) in q_old_nodes.iter() {
         if let Some(eye) = eye {
             commands.entity(eye.0).despawn();
@@ -188,103 +539,156 @@ fn clear(
     }
 }
 
+fn clear(mut commands: Commands, q_old_nodes: MapNodesQuery) {
+    clear_map(&mut commands, &q_old_nodes);
+}
+
 fn load_system(
     mut commands: Commands,
-    mut currency: ResMut<Currency>,
+    mut ledger: ResMut<Ledger>,
+    mut income: ResMut<IncomeStats>,
+    mut prestige: ResMut<Prestige>,
+    mut time_buffs: ResMut<TimeBuffs>,
+    mut random_map: ResMut<RandomForMap>,
+    mut map_slot: ResMut<MapSlot>,
     map_assets: Res<MapAssets>,
     highlights: Res<HighlightingMaterials>,
     q_loading_nodes: Query<(Entity, &LoadingPending)>,
     q_individual_node: Query<&LoadingNode>,
-    q_old_nodes: Query<(Entity, Option<&EyeCatcher>), Or<(With<ButtonRef>, With<BaseNode>)>>,
+    q_old_nodes: MapNodesQuery,
+    mut problems: ResMut<SaveProblems>,
 ) {
     for (e_loading, loading_pending) in q_loading_nodes.iter() {
-        for (e_old, eye) in q_old_nodes.iter() {
-            if let Some(eye) = eye {
-                commands.entity(eye.0).despawn();
-            }
-            commands.entity(e_old).despawn();
-        }
-        dbg!("removed all");
-        currency.amount = loading_pending.currencies;
+        clear_map(&mut commands, &q_old_nodes);
+        debug!("cleared the map for slot {}", loading_pending.slot);
+        *ledger = loading_pending.resources.clone();
+        income.restore(loading_pending.income.clone());
+        *prestige = loading_pending.prestige.clone();
+        *time_buffs = loading_pending.time_buffs.clone();
+        *random_map = RandomForMap::from_saved(&loading_pending.map_rng);
+        map_slot.slot = Some(loading_pending.slot);
+        // Only link to nodes which will actually be loaded.
+        let loadable: Vec<Option<Entity>> = loading_pending
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(index, e_node)| {
+                let is_loadable = q_individual_node.contains(*e_node);
+                if !is_loadable {
+                    problems
+                        .problems
+                        .push(SaveProblem::MissingLoadingNode { node: index });
+                }
+                is_loadable.then_some(*e_node)
+            })
+            .collect();
+        let to_entities = |indices: &Vec<usize>| -> Vec<Entity> {
+            indices
+                .iter()
+                .filter_map(|index| loadable.get(*index).copied().flatten())
+                .collect()
+        };
         for e_node in loading_pending.nodes.iter() {
-            let loading_node = q_individual_node.get(*e_node).unwrap();
+            let Ok(loading_node) = q_individual_node.get(*e_node) else {
+                continue;
+            };
             let blockers = Blockers {
-                entities: loading_node
-                    .0
-                    .blockers
-                    .iter()
-                    .map(|index| loading_pending.nodes[*index])
-                    .collect(),
+                entities: to_entities(&loading_node.0.blockers),
             };
             let to_block = ToBlock {
-                entities: loading_node
-                    .0
-                    .to_block
-                    .iter()
-                    .map(|index| loading_pending.nodes[*index])
-                    .collect(),
+                entities: to_entities(&loading_node.0.to_block),
             };
-            match loading_node.0.node_type {
-                NodeType::Blocker { is_blocked } => {
-                    insert_node(
-                        &mut commands,
-                        map_assets.mesh_blocker.clone(),
-                        &map_assets,
-                        &highlights,
-                        loading_node.0.pos,
-                        loading_node.0.timer_seconds_duration,
-                        loading_node.0.timer_seconds_duration - loading_node.0.timer_seconds_left,
-                        *e_node,
-                    );
-                    commands
-                        .entity(*e_node)
-                        .insert(NodeManualBlockToggle { is_blocked })
-                        .insert(SelfBlockStatus { is_blocked: true });
-
-                    commands.entity(*e_node).insert(blockers);
-                    commands.entity(*e_node).insert(to_block);
-                }
-                NodeType::Save { level } => {
-                    insert_node(
-                        &mut commands,
-                        map_assets.mesh_save.clone(),
-                        &map_assets,
-                        &highlights,
-                        loading_node.0.pos,
-                        loading_node.0.timer_seconds_duration,
-                        loading_node.0.timer_seconds_duration - loading_node.0.timer_seconds_left,
-                        *e_node,
-                    );
-                    commands.entity(*e_node).insert(NodeTextValidate {
-                        text: "Save".to_string(),
-                    });
-                    commands.entity(*e_node).insert(NodeSave { level });
-                    commands.entity(*e_node).insert(blockers);
-                    commands.entity(*e_node).insert(to_block);
-                }
-                NodeType::Gain { level } => {
-                    insert_node(
-                        &mut commands,
-                        map_assets.mesh_gain.clone(),
-                        &map_assets,
-                        &highlights,
-                        loading_node.0.pos,
-                        loading_node.0.timer_seconds_duration,
-                        loading_node.0.timer_seconds_duration - loading_node.0.timer_seconds_left,
-                        *e_node,
-                    );
-                    commands.entity(*e_node).insert(NodeCurrencyGain { level });
-
-                    commands.entity(*e_node).insert(NodeTextValidate {
-                        text: "Gain!".to_string(),
-                    });
-                    commands.entity(*e_node).insert(blockers);
-                    commands.entity(*e_node).insert(to_block);
-                }
-            }
+            let node = &loading_node.0;
+            let (mesh, label) = node_visuals(&node.node_type, &map_assets);
+            insert_node(
+                &mut commands,
+                mesh,
+                &map_assets,
+                &highlights,
+                node.pos,
+                node.timer_seconds_duration,
+                node.timer_seconds_duration - node.timer_seconds_left,
+                *e_node,
+            );
+            insert_behaviour(&mut commands.entity(*e_node), &node.node_type, label);
+            commands.entity(*e_node).insert(blockers).insert(to_block);
+            commands
+                .entity(*e_node)
+                .insert(loading_node.0.upgrades.clone())
+                .insert(loading_node.0.rule.clone());
             commands.entity(*e_node).remove::<LoadingNode>();
         }
-        dbg!("loadedall");
+        debug!("loaded slot {}", loading_pending.slot);
         commands.entity(e_loading).despawn();
     }
 }
+
+/// Saves built for tests, from [`default_save_with_seed`].
+#[cfg(test)]
+pub mod test_saves {
+    use super::*;
+
+    /// A node at the origin, without links, upgrades nor rule.
+    pub fn node(node_type: NodeType, duration: f32, seconds_left: f32) -> SavedNode {
+        SavedNode {
+            pos: Vec2::ZERO,
+            node_type,
+            timer_seconds_duration: duration,
+            timer_seconds_left: seconds_left,
+            to_block: vec![],
+            blockers: vec![],
+            upgrades: NodeUpgrades::default(),
+            rule: NodeRule::default(),
+        }
+    }
+
+    /// A coins gain [`node`].
+    pub fn gain(duration: f32, seconds_left: f32) -> SavedNode {
+        let gain = NodeType::Gain {
+            level: 1,
+            resource: ResourceKind::Coins,
+        };
+        node(gain, duration, seconds_left)
+    }
+
+    /// A new game playing `nodes`.
+    pub fn save_with(nodes: Vec<SavedNode>) -> Save {
+        let mut save = default_save_with_seed(0);
+        save.nodes = nodes;
+        save
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unreadable_save_is_backed_up() {
+        let raw = serde_json::json!({ "version": 1, "nodes": "not nodes" });
+        let mut backup = None;
+        let result = read_or_back_up(raw.clone(), 3, |key, raw| {
+            backup = Some((key, raw.clone()));
+            Ok(())
+        });
+        assert!(matches!(result, Err(LoadError::BackedUp(_, 3))));
+        assert_eq!(backup, Some(("slot_3_backup".to_string(), raw)));
+    }
+
+    #[test]
+    fn failed_backup_is_reported() {
+        let raw = serde_json::json!("not a save");
+        let result = read_or_back_up(raw, 3, |_, _| Err("storage full".to_string()));
+        let Err(LoadError::Unreadable(reason)) = result else {
+            panic!("backup failure should make the save unreadable");
+        };
+        assert!(reason.contains("storage full"));
+    }
+
+    #[test]
+    fn readable_save_is_not_backed_up() {
+        let raw = serde_json::to_value(default_save_with_seed(7)).unwrap();
+        let save = read_or_back_up(raw, 3, |_, _| panic!("nothing to back up")).unwrap();
+        assert_eq!(save.map_rng.seed, 7);
+    }
+}
@@ -1,12 +1,13 @@
+use crate::automation::ActivateNode;
+use crate::balance::Balance;
+use crate::income_stats::ResourceGained;
+use crate::multiplier::{consume_multipliers, MultiplierQuery};
 use crate::persisted_game::EventSave;
+use crate::prestige::Prestige;
+use crate::save_slots::SaveSlots;
+use crate::upgrade_shop::NodeUpgrades;
 use crate::*;
 
-use bevy::utils::Uuid;
-
-use bevy_picking_core::events::PointerEvent;
-use picking_core::backend::HitData;
-use picking_core::pointer::Location;
-
 #[derive(Resource)]
 pub struct HighlightingMaterials {
     pub mat_normal: Handle<ColorMaterial>,
@@ -44,28 +45,31 @@ pub fn setup(mut commands: Commands, mut materials: ResMut<Assets<ColorMaterial>
 }
 
 pub fn node_save_react(
-    mut events: EventReader<PointerEvent<Down>>,
-    mut q_nodes: Query<(&mut Progress, &mut NodeSave, &InheritedBlockStatus)>,
+    mut events: EventReader<ActivateNode>,
+    mut q_nodes: Query<(
+        &mut Progress,
+        &mut NodeSave,
+        &NodeUpgrades,
+        &InheritedBlockStatus,
+    )>,
     mut event_save: EventWriter<EventSave>,
-    currency: Res<Currency>,
+    ledger: Res<Ledger>,
+    slots: Res<SaveSlots>,
+    balance: Res<Balance>,
 ) {
     for event in events.iter() {
-        let e = event.target;
-        let Ok((mut p, mut node, status)) = q_nodes.get_mut(e) else {
+        let e = event.0;
+        let Ok((mut p, mut node, upgrades, status)) = q_nodes.get_mut(e) else {
             continue;
         };
         if status.is_blocked {
             continue;
         }
         if p.timer.finished() {
-            p.timer.set_duration(Duration::from_secs_f32(
-                TIMER_SAVE_BASE
-                    + node.level as f32 * TIMER_SAVE_MULT_PER_LEVEL
-                    + (currency.amount as f32 * TIMER_SAVE_ADD_MULT_PER_CURRENCY),
-            ));
-            p.timer.reset();
-            node.level += 1;
-            event_save.send(EventSave);
+            node.click(&mut *p, upgrades, ledger.get(ResourceKind::Coins), &balance);
+            event_save.send(EventSave {
+                slot: slots.current,
+            });
         } else {
             //dbg!("NOT READY");
         }
@@ -73,11 +77,11 @@ pub fn node_save_react(
 }
 
 pub fn node_manual_toggle_block_react(
-    mut events: EventReader<PointerEvent<Down>>,
+    mut events: EventReader<ActivateNode>,
     mut q_nodes: Query<(&Progress, &mut NodeManualBlockToggle, &InheritedBlockStatus)>,
 ) {
     for event in events.iter() {
-        let e = event.target;
+        let e = event.0;
         let Ok((p, mut node, status)) = q_nodes.get_mut(e) else {
             continue;
         };
@@ -95,62 +99,55 @@ pub fn node_manual_toggle_block_react(
 }
 
 pub fn node_gain_react(
-    mut events: EventReader<PointerEvent<Down>>,
+    mut events: EventReader<ActivateNode>,
     mut events_writer: EventWriter<NewNodeEvent>,
     mut events_reset_writer: EventWriter<PropagateResetManualButtons>,
-    mut q_timer: Query<(&mut Progress, &InheritedBlockStatus, &mut NodeCurrencyGain)>,
-    mut currencies: ResMut<Currency>,
+    mut q_timer: Query<(
+        &Transform,
+        &mut Progress,
+        &InheritedBlockStatus,
+        &mut NodeCurrencyGain,
+        &NodeUpgrades,
+    )>,
+    mut q_multipliers: MultiplierQuery,
+    mut ledger: ResMut<Ledger>,
+    mut events_gained: EventWriter<ResourceGained>,
+    prestige: Res<Prestige>,
+    balance: Res<Balance>,
 ) {
     for event in events.iter() {
-        let e = event.target;
-        let Ok((mut p, status, mut gain)) = q_timer.get_mut(e) else {
-                continue;
-            };
+        let e = event.0;
+        let Ok((transform, mut p, status, mut gain, upgrades)) = q_timer.get_mut(e) else {
+            continue;
+        };
         if !status.is_blocked && p.timer.finished() {
-            currencies.amount += 1;
-            let new_time_duration = currencies.amount as f32 * TIMER_GAIN_MULT
-                + TIMER_GAIN_MULT_PER_LEVEL * gain.level as f32;
-            p.timer
-                .set_duration(Duration::from_secs_f32(new_time_duration));
-            p.timer.reset();
-            gain.level += 1;
+            let multipliers = q_multipliers.iter_mut().map(
+                |(transform, progress, multiplier, upgrades, status)| {
+                    (
+                        transform.translation.truncate(),
+                        NodeMultiplier {
+                            level: multiplier.level,
+                        },
+                        upgrades.duration_factor(),
+                        status.is_blocked,
+                        progress,
+                    )
+                },
+            );
+            let boost =
+                consume_multipliers(transform.translation.truncate(), multipliers, &balance);
+            let gained = gain.click(&mut *p, upgrades, boost, &mut ledger, &prestige, &balance);
+            events_gained.send(ResourceGained {
+                resource: gain.resource,
+                amount: gained,
+            });
             events_writer.send(NewNodeEvent {
                 entity: e,
-                currencies_on_click: currencies.amount,
+                currencies_on_click: ledger.get(ResourceKind::Coins),
             });
             events_reset_writer.send(PropagateResetManualButtons(e));
         } else {
-            dbg!("NOT READY");
-        }
-    }
-}
-
-#[derive(Component)]
-pub struct AutoClick(pub Entity);
-
-pub fn auto_click(
-    mut events: EventWriter<PointerEvent<Down>>,
-    q_autoclick: Query<(&Visibility, &AutoClick)>,
-) {
-    for (v, auto_click) in q_autoclick.iter() {
-        if v == Visibility::Visible {
-            events.send(PointerEvent::<Down>::new(
-                PointerId::Custom(Uuid::new_v4()),
-                Location {
-                    target: bevy::render::camera::NormalizedRenderTarget::Image(Handle::default()),
-                    position: Vec2::default(),
-                },
-                auto_click.0,
-                Down {
-                    button: PointerButton::Primary,
-                    hit: HitData {
-                        camera: Entity::PLACEHOLDER,
-                        depth: 1f32,
-                        position: None,
-                        normal: None,
-                    },
-                },
-            ))
+            trace!("clicked {:?} before its timer finished", e);
         }
     }
 }
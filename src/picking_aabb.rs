@@ -73,9 +73,10 @@ pub fn half_extents_picking(
 
         let Some(cursor_position) = camera
             .viewport_to_world(camera_transform, cursor_position)
-            .map(|ray| ray.get_point(0f32)) else {
-                continue;
-            };
+            .map(|ray| ray.get_point(0f32))
+        else {
+            continue;
+        };
         let over_list = data_query
             .iter()
             .filter_map(|(entity, aabb, global_transform, visibility, focus)| {
@@ -0,0 +1,253 @@
+use bevy::{input::common_conditions::input_just_pressed, prelude::*};
+use rand::{thread_rng, Rng};
+use serde::{Deserialize, Serialize};
+
+use crate::balance::Balance;
+use crate::big_number::BigNumber;
+use crate::idle_gains::ResourceKind;
+use crate::income_stats::ResourceGained;
+use crate::number_format::format_number;
+use crate::persisted_game::{self, default_save_with_seed};
+use crate::save_slots::{is_typing, SaveSlots, BUTTON_COLOR, BUTTON_HOVERED_COLOR};
+use crate::save_validation::SaveProblems;
+use crate::settings::Settings;
+use crate::MapAssets;
+
+/// Gain timers get shorter by this much per point.
+const TIMER_BONUS_PER_POINT: f32 = 0.05f32;
+/// Gains produce this much more per point.
+const YIELD_BONUS_PER_POINT: f64 = 0.1f64;
+
+pub struct PrestigePlugin;
+
+impl Plugin for PrestigePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Prestige>()
+            .init_resource::<PrestigePanel>()
+            .add_system(track_run_coins)
+            .add_system(
+                toggle_panel
+                    .run_if(input_just_pressed(KeyCode::R))
+                    .run_if(not(is_typing)),
+            )
+            .add_system(prestige_button_react)
+            .add_system(prestige_button_hover)
+            .add_system(update_panel_ui.after(prestige_button_react));
+    }
+}
+
+/// Meta progression, kept through prestige resets.
+#[derive(Resource, Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct Prestige {
+    pub points: BigNumber,
+    pub resets: u32,
+    /// Coins earned since the last reset, which prestige points are computed from.
+    pub run_coins_earned: BigNumber,
+}
+
+impl Prestige {
+    /// Points a prestige would give now, growing with coins earned to the power set in `balance`.
+    pub fn pending_points(&self, balance: &Balance) -> BigNumber {
+        (self.run_coins_earned / BigNumber::from(balance.prestige_base_coins as f64))
+            .powf(balance.prestige_power as f64)
+            .floor()
+    }
+
+    /// Multiplier for gain timers durations.
+    pub fn timer_factor(&self) -> f32 {
+        1f32 / (1f32 + self.points.to_f32() * TIMER_BONUS_PER_POINT)
+    }
+
+    /// Counts coins produced towards the next prestige.
+    pub fn track(&mut self, resource: ResourceKind, amount: BigNumber) {
+        if resource == ResourceKind::Coins {
+            self.run_coins_earned += amount;
+        }
+    }
+
+    /// Multiplier for resources produced by gains.
+    pub fn yield_multiplier(&self) -> BigNumber {
+        BigNumber::ONE + self.points * YIELD_BONUS_PER_POINT
+    }
+}
+
+fn track_run_coins(mut prestige: ResMut<Prestige>, mut events: EventReader<ResourceGained>) {
+    for event in events.iter() {
+        prestige.track(event.resource, event.amount);
+    }
+}
+
+#[derive(Resource, Default)]
+struct PrestigePanel {
+    is_open: bool,
+}
+
+#[derive(Clone, Copy)]
+enum PrestigeAction {
+    Prestige,
+    Close,
+}
+
+#[derive(Component)]
+struct PrestigeButton(PrestigeAction);
+
+#[derive(Component)]
+struct PrestigePanelNode;
+
+fn toggle_panel(mut panel: ResMut<PrestigePanel>) {
+    panel.is_open = !panel.is_open;
+}
+
+/// Starts a new map, trading the run for prestige points.
+fn prestige_button_react(
+    mut commands: Commands,
+    slots: Res<SaveSlots>,
+    balance: Res<Balance>,
+    mut prestige: ResMut<Prestige>,
+    mut panel: ResMut<PrestigePanel>,
+    mut problems: ResMut<SaveProblems>,
+    q_buttons: Query<(&Interaction, &PrestigeButton), Changed<Interaction>>,
+) {
+    for (interaction, button) in q_buttons.iter() {
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+        match button.0 {
+            PrestigeAction::Prestige => {
+                let points = prestige.pending_points(&balance);
+                if points.is_zero() {
+                    continue;
+                }
+                let mut save = default_save_with_seed(thread_rng().gen());
+                save.prestige = Prestige {
+                    points: prestige.points + points,
+                    resets: prestige.resets + 1,
+                    run_coins_earned: BigNumber::ZERO,
+                };
+                info!("prestige for {} points", points);
+                // Loading clears the current map, and sets the new prestige.
+                let load_problems = persisted_game::start_load(&mut commands, &save, slots.current);
+                problems.report("prestige", load_problems);
+                // Avoids prestiging twice before the load happens.
+                prestige.run_coins_earned = BigNumber::ZERO;
+                panel.is_open = false;
+            }
+            PrestigeAction::Close => {
+                panel.is_open = false;
+            }
+        }
+    }
+}
+
+fn prestige_button_hover(
+    mut q_buttons: Query<
+        (&Interaction, &mut BackgroundColor),
+        (Changed<Interaction>, With<PrestigeButton>),
+    >,
+) {
+    for (interaction, mut color) in q_buttons.iter_mut() {
+        *color = match interaction {
+            Interaction::Hovered => BUTTON_HOVERED_COLOR.into(),
+            _ => BUTTON_COLOR.into(),
+        };
+    }
+}
+
+fn update_panel_ui(
+    mut commands: Commands,
+    map_assets: Res<MapAssets>,
+    panel: Res<PrestigePanel>,
+    prestige: Res<Prestige>,
+    balance: Res<Balance>,
+    settings: Res<Settings>,
+    q_panel: Query<Entity, With<PrestigePanelNode>>,
+) {
+    if !panel.is_changed()
+        && !prestige.is_changed()
+        && !balance.is_changed()
+        && !settings.is_changed()
+    {
+        return;
+    }
+    for panel in q_panel.iter() {
+        commands.entity(panel).despawn_recursive();
+    }
+    if !panel.is_open {
+        return;
+    }
+    let text_style = TextStyle {
+        color: Color::WHITE,
+        ..map_assets.text_style.clone()
+    };
+    let button = |parent: &mut ChildBuilder, label: &str, action: PrestigeAction| {
+        parent
+            .spawn((
+                ButtonBundle {
+                    style: Style {
+                        margin: UiRect::all(Val::Px(4.0)),
+                        padding: UiRect::all(Val::Px(6.0)),
+                        ..default()
+                    },
+                    background_color: BUTTON_COLOR.into(),
+                    ..default()
+                },
+                PrestigeButton(action),
+            ))
+            .with_children(|parent| {
+                parent.spawn(TextBundle::from_section(label, text_style.clone()));
+            });
+    };
+    let format = |n: BigNumber| format_number(n, settings.notation);
+    let pending = prestige.pending_points(&balance);
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        top: Val::Px(120.0),
+                        left: Val::Percent(35.0),
+                        ..default()
+                    },
+                    flex_direction: FlexDirection::Column,
+                    padding: UiRect::all(Val::Px(10.0)),
+                    ..default()
+                },
+                background_color: Color::rgba(0.1, 0.1, 0.1, 0.9).into(),
+                ..default()
+            },
+            PrestigePanelNode,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                format!(
+                    "Prestige (R): {} points, {} resets\n\
+                     gains x{:.2}, gain timers x{:.2}\n\
+                     {} coins earned this run",
+                    format(prestige.points),
+                    prestige.resets,
+                    prestige.yield_multiplier().to_f64(),
+                    prestige.timer_factor(),
+                    format(prestige.run_coins_earned),
+                ),
+                text_style.clone(),
+            ));
+            if pending.is_zero() {
+                parent.spawn(TextBundle::from_section(
+                    format!(
+                        "earn {} coins in a run to prestige",
+                        format(BigNumber::from(balance.prestige_base_coins as f64))
+                    ),
+                    text_style.clone(),
+                ));
+            } else {
+                button(
+                    parent,
+                    &format!("Reset the map for {} points", format(pending)),
+                    PrestigeAction::Prestige,
+                );
+            }
+            button(parent, "Close", PrestigeAction::Close);
+        });
+}
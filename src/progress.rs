@@ -1,5 +1,14 @@
+use std::time::Duration;
+
 use bevy::prelude::*;
 
+use crate::balance::Balance;
+use crate::bank::NodeBank;
+use crate::big_number::BigNumber;
+use crate::number_format::format_number;
+use crate::overclock::TimeBuffs;
+use crate::persisted_game::SavedNode;
+use crate::settings::Settings;
 use crate::*;
 
 #[derive(Component, Debug)]
@@ -7,6 +16,71 @@ pub struct Progress {
     pub timer: Timer,
 }
 
+/// Timer of a node, live in [`Progress`] or saved in a [`SavedNode`],
+/// so the game and the [`Simulation`](crate::simulation::Simulation) share their rules.
+pub trait NodeTimer {
+    fn is_finished(&self) -> bool;
+    /// Starts over, lasting `seconds`.
+    fn restart(&mut self, seconds: f32);
+    fn finish(&mut self);
+    /// Multiplies the duration, keeping the time elapsed.
+    fn scale_duration(&mut self, factor: f32);
+}
+
+impl NodeTimer for Progress {
+    fn is_finished(&self) -> bool {
+        self.timer.finished()
+    }
+
+    fn restart(&mut self, seconds: f32) {
+        self.timer.set_duration(Duration::from_secs_f32(seconds));
+        self.timer.reset();
+    }
+
+    fn finish(&mut self) {
+        let remaining = self.timer.remaining();
+        self.timer.tick(remaining);
+    }
+
+    fn scale_duration(&mut self, factor: f32) {
+        let duration = self.timer.duration().mul_f32(factor);
+        self.timer.set_duration(duration);
+    }
+}
+
+impl NodeTimer for SavedNode {
+    fn is_finished(&self) -> bool {
+        self.timer_seconds_left <= 0f32
+    }
+
+    fn restart(&mut self, seconds: f32) {
+        self.timer_seconds_duration = seconds;
+        self.timer_seconds_left = seconds;
+    }
+
+    fn finish(&mut self) {
+        self.timer_seconds_left = 0f32;
+    }
+
+    fn scale_duration(&mut self, factor: f32) {
+        let elapsed = self.timer_seconds_duration - self.timer_seconds_left;
+        self.timer_seconds_duration *= factor;
+        self.timer_seconds_left = (self.timer_seconds_duration - elapsed).max(0f32);
+    }
+}
+
+/// How fast the timer of a node goes, `manual_blocked` being the toggle of blockers.
+///
+/// Blocked nodes slow down, blockers toggled on stop while blocked.
+pub fn tick_rate(manual_blocked: Option<bool>, inherited_blocked: bool, balance: &Balance) -> f32 {
+    match manual_blocked {
+        Some(is_blocked) if is_blocked && inherited_blocked => 0f32,
+        Some(_) => 1f32,
+        None if inherited_blocked => 1f32 / balance.blocked_timer_slowdown,
+        None => 1f32,
+    }
+}
+
 #[derive(Component, Debug)]
 pub struct NodeTextValidate {
     pub text: String,
@@ -14,27 +88,31 @@ pub struct NodeTextValidate {
 
 pub fn update_progress_timer(
     time: Res<Time>,
+    balance: Res<Balance>,
+    buffs: Res<TimeBuffs>,
     mut q_timer: Query<(
+        &Transform,
         &mut Progress,
         &InheritedBlockStatus,
         Option<&NodeManualBlockToggle>,
     )>,
 ) {
-    for (mut t, status, manual) in q_timer.iter_mut() {
-        if let Some(manual) = manual {
-            if manual.is_blocked && status.is_blocked {
-                continue;
-            }
-        } else if status.is_blocked {
-            t.timer.tick(time.delta().div_f32(20f32));
+    for (transform, mut t, status, manual) in q_timer.iter_mut() {
+        let rate = tick_rate(
+            manual.map(|manual| manual.is_blocked),
+            status.is_blocked,
+            &balance,
+        );
+        if rate <= 0f32 {
             continue;
         }
-        t.timer.tick(time.delta());
+        let time_scale = buffs.time_scale_at(transform.translation.truncate());
+        t.timer.tick(time.delta().mul_f32(rate * time_scale));
     }
 }
 
 pub fn update_progress_manual_auto_block(
-    currencies: Res<Currency>,
+    ledger: Res<Ledger>,
     mut events_writer: EventWriter<NewNodeEvent>,
     mut q_timer: Query<(Entity, &Progress, &mut NodeManualBlockToggle)>,
 ) {
@@ -43,13 +121,14 @@ pub fn update_progress_manual_auto_block(
             status.is_blocked = true;
             events_writer.send(NewNodeEvent {
                 entity: e,
-                currencies_on_click: currencies.amount,
+                currencies_on_click: ledger.get(ResourceKind::Coins),
             });
         }
     }
 }
 
 pub fn update_progress_text(
+    settings: Res<Settings>,
     mut q_texts: Query<(&mut Text, &ButtonRef)>,
     q_timer: Query<
         (
@@ -58,6 +137,7 @@ pub fn update_progress_text(
             &SelfBlockStatus,
             Option<&NodeManualBlockToggle>,
             Option<&NodeTextValidate>,
+            Option<&NodeBank>,
         ),
         Or<(Changed<Progress>, Changed<InheritedBlockStatus>)>,
     >,
@@ -67,7 +147,7 @@ pub fn update_progress_text(
             Err(_) => {
                 t.sections[0].value = "error".into();
             }
-            Ok((p, status, self_status, manual_toggle, text_validate)) => {
+            Ok((p, status, self_status, manual_toggle, text_validate, bank)) => {
                 if status.is_blocked {
                     t.sections[0].value = "Blocked".to_string();
                     continue;
@@ -82,9 +162,19 @@ pub fn update_progress_text(
                     } else {
                         text_validate.map_or("???", |t| &t.text)
                     };
-                    t.sections[0].value = text.to_string();
+                    // A bank done growing waits for a withdrawal.
+                    t.sections[0].value = match bank.filter(|bank| bank.holds_deposit()) {
+                        Some(bank) => format!("{}\nWithdraw", bank.label(settings.notation)),
+                        None => text.to_string(),
+                    };
                 } else {
-                    t.sections[0].value = format!("{:.0}s", p.timer.remaining_secs().ceil());
+                    let remaining = BigNumber::from(p.timer.remaining_secs().ceil() as f64);
+                    let remaining = format!("{}s", format_number(remaining, settings.notation));
+                    // Banks show their deposit while it grows.
+                    t.sections[0].value = match bank.filter(|bank| bank.holds_deposit()) {
+                        Some(bank) => format!("{}\n{}", bank.label(settings.notation), remaining),
+                        None => remaining,
+                    };
                 }
             }
         }
@@ -0,0 +1,264 @@
+use std::collections::VecDeque;
+
+use bevy::{input::common_conditions::input_just_pressed, prelude::*};
+use bevy_pkv::PkvStore;
+use serde::{Deserialize, Serialize};
+
+use crate::big_number::BigNumber;
+use crate::idle_gains::ResourceKind;
+use crate::number_format::format_number;
+use crate::offline_progress::format_duration;
+use crate::persisted_game::{self, now_since2023, LastSave, Save};
+use crate::save_slots::{is_typing, SaveSlots, SlotId, BUTTON_COLOR, BUTTON_HOVERED_COLOR};
+use crate::save_validation::SaveProblems;
+use crate::settings::Settings;
+use crate::MapAssets;
+
+/// Amount of previous saves kept per slot.
+const HISTORY_SIZE: usize = 20;
+/// Saves closer than this to the latest entry are not kept,
+/// so the history goes back hours rather than a few autosaves.
+const HISTORY_INTERVAL_SECONDS: f64 = 600f64;
+
+pub struct SaveHistoryPlugin;
+
+impl Plugin for SaveHistoryPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<HistoryPanel>()
+            .add_system(
+                toggle_panel
+                    .run_if(input_just_pressed(KeyCode::H))
+                    .run_if(not(is_typing)),
+            )
+            .add_system(history_button_react)
+            .add_system(history_button_hover)
+            .add_system(update_panel_ui.after(history_button_react));
+    }
+}
+
+pub fn history_key(slot: SlotId) -> String {
+    format!("slot_{}_history", slot)
+}
+
+/// A previous save, with enough information to choose it without loading it.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct HistoryEntry {
+    pub time_since2023: f64,
+    /// Coins owned in this save.
+    pub currencies: BigNumber,
+    pub node_count: usize,
+    /// Kept raw to be migrated on restore, like any other save.
+    pub save: serde_json::Value,
+}
+
+/// Ring buffer of the latest saves of a slot, most recent first.
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct SaveHistory {
+    pub entries: VecDeque<HistoryEntry>,
+}
+
+impl SaveHistory {
+    pub fn load(pkv: &PkvStore, slot: SlotId) -> SaveHistory {
+        pkv.get::<SaveHistory>(history_key(slot))
+            .unwrap_or_default()
+    }
+
+    /// Adds `save` to the history of `slot`, forgetting the oldest entry if full.
+    ///
+    /// Skipped if the latest entry is less than [`HISTORY_INTERVAL_SECONDS`] older.
+    pub fn push(pkv: &mut PkvStore, slot: SlotId, save: &Save) {
+        let mut history = SaveHistory::load(pkv, slot);
+        if let Some(latest) = history.entries.front() {
+            let since_latest = save.last_tick_time_since2023 - latest.time_since2023;
+            if (0f64..HISTORY_INTERVAL_SECONDS).contains(&since_latest) {
+                return;
+            }
+        }
+        let Ok(raw) = serde_json::to_value(save) else {
+            return;
+        };
+        history.entries.push_front(HistoryEntry {
+            time_since2023: save.last_tick_time_since2023,
+            currencies: save.resources.get(ResourceKind::Coins),
+            node_count: save.nodes.len(),
+            save: raw,
+        });
+        history.entries.truncate(HISTORY_SIZE);
+        if let Err(err) = pkv.set(history_key(slot), &history) {
+            error!("could not save history of slot {}: {}", slot, err);
+        }
+    }
+}
+
+#[derive(Resource, Default)]
+struct HistoryPanel {
+    is_open: bool,
+}
+
+#[derive(Clone, Copy)]
+enum HistoryAction {
+    Restore(usize),
+    Close,
+}
+
+#[derive(Component)]
+struct HistoryButton(HistoryAction);
+
+#[derive(Component)]
+struct HistoryPanelNode;
+
+fn toggle_panel(mut panel: ResMut<HistoryPanel>) {
+    panel.is_open = !panel.is_open;
+}
+
+/// Restores into a new slot, the played one is left as it was.
+fn history_button_react(
+    mut commands: Commands,
+    mut pkv: ResMut<PkvStore>,
+    mut slots: ResMut<SaveSlots>,
+    mut panel: ResMut<HistoryPanel>,
+    mut problems: ResMut<SaveProblems>,
+    q_buttons: Query<(&Interaction, &HistoryButton), Changed<Interaction>>,
+) {
+    for (interaction, button) in q_buttons.iter() {
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+        match button.0 {
+            HistoryAction::Restore(index) => {
+                let history = SaveHistory::load(&pkv, slots.current);
+                let Some(entry) = history.entries.get(index) else {
+                    continue;
+                };
+                match persisted_game::parse_save(entry.save.clone()) {
+                    Ok(save) => {
+                        let name = format!(
+                            "{} (restored)",
+                            slots.get(slots.current).map_or("Save", |slot| &slot.name)
+                        );
+                        let id = slots.create_with_save(&mut pkv, name, &save);
+                        slots.set_current(&mut pkv, id);
+                        let load_problems = persisted_game::start_load(&mut commands, &save, id);
+                        problems.report("restore", load_problems);
+                        panel.is_open = false;
+                    }
+                    Err(err) => error!("could not restore save: {}", err),
+                }
+            }
+            HistoryAction::Close => {
+                panel.is_open = false;
+            }
+        }
+    }
+}
+
+fn history_button_hover(
+    mut q_buttons: Query<
+        (&Interaction, &mut BackgroundColor),
+        (Changed<Interaction>, With<HistoryButton>),
+    >,
+) {
+    for (interaction, mut color) in q_buttons.iter_mut() {
+        *color = match interaction {
+            Interaction::Hovered => BUTTON_HOVERED_COLOR.into(),
+            _ => BUTTON_COLOR.into(),
+        };
+    }
+}
+
+fn update_panel_ui(
+    mut commands: Commands,
+    map_assets: Res<MapAssets>,
+    pkv: Res<PkvStore>,
+    slots: Res<SaveSlots>,
+    panel: Res<HistoryPanel>,
+    last_save: Res<LastSave>,
+    settings: Res<Settings>,
+    q_panel: Query<Entity, With<HistoryPanelNode>>,
+) {
+    if !panel.is_changed() && !last_save.is_changed() && !slots.is_changed() {
+        return;
+    }
+    for panel in q_panel.iter() {
+        commands.entity(panel).despawn_recursive();
+    }
+    if !panel.is_open {
+        return;
+    }
+    let text_style = TextStyle {
+        color: Color::WHITE,
+        ..map_assets.text_style.clone()
+    };
+    let button = |parent: &mut ChildBuilder, label: &str, action: HistoryAction| {
+        parent
+            .spawn((
+                ButtonBundle {
+                    style: Style {
+                        margin: UiRect::all(Val::Px(4.0)),
+                        padding: UiRect::all(Val::Px(6.0)),
+                        ..default()
+                    },
+                    background_color: BUTTON_COLOR.into(),
+                    ..default()
+                },
+                HistoryButton(action),
+            ))
+            .with_children(|parent| {
+                parent.spawn(TextBundle::from_section(label, text_style.clone()));
+            });
+    };
+    let history = SaveHistory::load(&pkv, slots.current);
+    let now = now_since2023();
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        top: Val::Px(120.0),
+                        right: Val::Px(15.0),
+                        ..default()
+                    },
+                    flex_direction: FlexDirection::Column,
+                    padding: UiRect::all(Val::Px(10.0)),
+                    ..default()
+                },
+                background_color: Color::rgba(0.1, 0.1, 0.1, 0.9).into(),
+                ..default()
+            },
+            HistoryPanelNode,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "Save history (H)",
+                text_style.clone(),
+            ));
+            if history.entries.is_empty() {
+                parent.spawn(TextBundle::from_section("No save yet", text_style.clone()));
+            }
+            for (index, entry) in history.entries.iter().enumerate() {
+                parent
+                    .spawn(NodeBundle {
+                        style: Style {
+                            flex_direction: FlexDirection::Row,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        ..default()
+                    })
+                    .with_children(|parent| {
+                        parent.spawn(TextBundle::from_section(
+                            format!(
+                                "{} ago: {} coins, {} nodes",
+                                format_duration((now - entry.time_since2023).max(0f64) as f32),
+                                format_number(entry.currencies, settings.notation),
+                                entry.node_count
+                            ),
+                            text_style.clone(),
+                        ));
+                        button(parent, "Restore", HistoryAction::Restore(index));
+                    });
+            }
+            button(parent, "Close", HistoryAction::Close);
+        });
+}
@@ -0,0 +1,229 @@
+use rand::{thread_rng, Rng};
+use serde_json::{json, Value};
+
+use crate::big_number::BigNumber;
+use crate::persisted_game::now_since2023;
+
+/// Version written in new saves, bump it when adding a migration.
+///
+/// Adding a field with `#[serde(default)]`, or a new node type, doesn't need a new version:
+/// older saves don't have it and read as the default, e.g. node `upgrades`, `rule` and
+/// the save `time_buffs`. A migration is only needed when existing data changes
+/// meaning or layout.
+pub const CURRENT_SAVE_VERSION: u32 = 4;
+
+type Migration = fn(Value) -> Result<Value, String>;
+
+/// `MIGRATIONS[n]` converts a save from version `n` to version `n + 1`.
+const MIGRATIONS: [Migration; CURRENT_SAVE_VERSION as usize] =
+    [v0_to_v1, v1_to_v2, v2_to_v3, v3_to_v4];
+
+/// Saves without a `version` field predate versioning, they are version 0.
+pub fn save_version(save: &Value) -> u32 {
+    save.get("version")
+        .and_then(Value::as_u64)
+        .map_or(0, |v| v as u32)
+}
+
+/// Applies all migrations needed to bring `save` to [`CURRENT_SAVE_VERSION`].
+pub fn migrate(mut save: Value) -> Result<Value, MigrationError> {
+    let version = save_version(&save);
+    if version > CURRENT_SAVE_VERSION {
+        return Err(MigrationError::TooRecent(version));
+    }
+    for (from, migration) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+        save = migration(save).map_err(|reason| MigrationError::Failed {
+            from: from as u32,
+            reason,
+        })?;
+        save["version"] = Value::from(from as u32 + 1);
+    }
+    Ok(save)
+}
+
+#[derive(Debug)]
+pub enum MigrationError {
+    TooRecent(u32),
+    Failed { from: u32, reason: String },
+}
+
+impl std::fmt::Display for MigrationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MigrationError::TooRecent(version) => write!(
+                f,
+                "save version {} is more recent than supported version {}",
+                version, CURRENT_SAVE_VERSION
+            ),
+            MigrationError::Failed { from, reason } => write!(
+                f,
+                "migration from version {} to {} failed: {}",
+                from,
+                from + 1,
+                reason
+            ),
+        }
+    }
+}
+
+/// Version 0 saves always stored 2 as their last tick time,
+/// stamp them with the current time to avoid fast-forwarding since 2023.
+fn v0_to_v1(mut save: Value) -> Result<Value, String> {
+    let Some(save_object) = save.as_object_mut() else {
+        return Err("save is not an object".to_string());
+    };
+    save_object.insert(
+        "last_tick_time_since2023".to_string(),
+        Value::from(now_since2023()),
+    );
+    Ok(save)
+}
+
+/// Version 1 saves didn't store the map random generator, give them a new one.
+fn v1_to_v2(mut save: Value) -> Result<Value, String> {
+    let Some(save_object) = save.as_object_mut() else {
+        return Err("save is not an object".to_string());
+    };
+    save_object.insert(
+        "map_rng".to_string(),
+        json!({ "seed": thread_rng().gen::<u64>(), "word_pos": 0 }),
+    );
+    Ok(save)
+}
+
+/// Version 2 saves stored currencies as an integer, convert it to a big number.
+fn v2_to_v3(mut save: Value) -> Result<Value, String> {
+    let Some(save_object) = save.as_object_mut() else {
+        return Err("save is not an object".to_string());
+    };
+    let Some(currencies) = save_object.get("currencies").and_then(Value::as_i64) else {
+        return Err("currencies is not an integer".to_string());
+    };
+    let currencies = BigNumber::from(currencies as f64);
+    save_object.insert(
+        "currencies".to_string(),
+        json!({ "mantissa": currencies.mantissa(), "exponent": currencies.exponent() }),
+    );
+    Ok(save)
+}
+
+/// Version 3 saves only had coins, move them into the resource ledger.
+fn v3_to_v4(mut save: Value) -> Result<Value, String> {
+    let Some(save_object) = save.as_object_mut() else {
+        return Err("save is not an object".to_string());
+    };
+    let Some(currencies) = save_object.remove("currencies") else {
+        return Err("save has no currencies".to_string());
+    };
+    save_object.insert("resources".to_string(), json!({ "Coins": currencies }));
+    Ok(save)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{idle_gains::ResourceKind, persisted_game::parse_save};
+
+    /// A save as written before versioning.
+    fn v0_save() -> Value {
+        json!({
+            "currencies": 12,
+            "last_tick_time_since2023": 2.0,
+            "nodes": [{
+                "pos": [0.0, 0.0],
+                "node_type": { "Gain": { "level": 1 } },
+                "timer_seconds_duration": 2.0,
+                "timer_seconds_left": 1.5,
+                "to_block": [],
+                "blockers": [],
+            }],
+        })
+    }
+
+    fn v1_save() -> Value {
+        let mut save = v0_save();
+        save["version"] = json!(1);
+        save["last_tick_time_since2023"] = json!(1000.0);
+        save
+    }
+
+    fn v2_save() -> Value {
+        let mut save = v1_save();
+        save["version"] = json!(2);
+        save["map_rng"] = json!({ "seed": 42, "word_pos": 8 });
+        save
+    }
+
+    fn v3_save() -> Value {
+        let mut save = v2_save();
+        save["version"] = json!(3);
+        save["currencies"] = json!({ "mantissa": 1.2, "exponent": 1 });
+        save
+    }
+
+    #[test]
+    fn every_version_migrates_to_current() {
+        for (version, save) in [v0_save(), v1_save(), v2_save(), v3_save()]
+            .into_iter()
+            .enumerate()
+        {
+            assert_eq!(save_version(&save), version as u32);
+            let migrated = migrate(save).unwrap();
+            assert_eq!(save_version(&migrated), CURRENT_SAVE_VERSION);
+            let save = parse_save(migrated).unwrap();
+            assert_eq!(save.resources.get(ResourceKind::Coins), BigNumber::from(12));
+            assert_eq!(save.nodes.len(), 1);
+            assert_eq!(save.nodes[0].timer_seconds_left, 1.5);
+        }
+    }
+
+    #[test]
+    fn v0_is_stamped_with_the_current_time() {
+        let migrated = migrate(v0_save()).unwrap();
+        let last_tick = migrated["last_tick_time_since2023"].as_f64().unwrap();
+        assert!(last_tick > 2.0);
+        assert!(last_tick <= now_since2023());
+    }
+
+    #[test]
+    fn later_versions_keep_their_data() {
+        let save = parse_save(v1_save()).unwrap();
+        assert_eq!(save.last_tick_time_since2023, 1000.0);
+
+        let save = parse_save(v2_save()).unwrap();
+        assert_eq!(save.map_rng.seed, 42);
+        assert_eq!(save.map_rng.word_pos, 8);
+    }
+
+    #[test]
+    fn current_version_is_unchanged() {
+        let mut save = migrate(v3_save()).unwrap();
+        let before = save.clone();
+        save = migrate(save).unwrap();
+        assert_eq!(save, before);
+    }
+
+    #[test]
+    fn too_recent_version_fails() {
+        let mut save = v3_save();
+        save["version"] = json!(CURRENT_SAVE_VERSION + 1);
+        assert!(matches!(
+            migrate(save),
+            Err(MigrationError::TooRecent(version)) if version == CURRENT_SAVE_VERSION + 1
+        ));
+    }
+
+    #[test]
+    fn broken_save_reports_its_failing_migration() {
+        let mut save = v2_save();
+        save["currencies"] = json!("a lot");
+        assert!(matches!(
+            migrate(save),
+            Err(MigrationError::Failed { from: 2, .. })
+        ));
+        assert!(matches!(
+            migrate(json!([1, 2])),
+            Err(MigrationError::Failed { from: 0, .. })
+        ));
+    }
+}
@@ -0,0 +1,450 @@
+use bevy::{input::common_conditions::input_just_pressed, prelude::*};
+use bevy_pkv::PkvStore;
+use serde::{Deserialize, Serialize};
+
+use crate::persisted_game::{
+    default_save_with_seed, save_key, EventLoad, Save, SaveSnapshot, SaveWriter, LEGACY_SAVE_KEY,
+};
+use crate::save_history::{history_key, SaveHistory};
+use crate::MapAssets;
+
+const SLOTS_KEY: &str = "slots";
+
+pub const BUTTON_COLOR: Color = Color::rgb(0.25, 0.25, 0.25);
+pub const BUTTON_HOVERED_COLOR: Color = Color::rgb(0.35, 0.35, 0.35);
+const CURRENT_SLOT_COLOR: Color = Color::rgb(0.2, 0.45, 0.2);
+
+pub struct SaveSlotsPlugin;
+
+impl Plugin for SaveSlotsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SlotPicker>()
+            .add_system(
+                load_current_slot
+                    .in_schedule(CoreSchedule::Startup)
+                    .in_base_set(StartupSet::PostStartup),
+            )
+            .add_system(
+                toggle_picker
+                    .run_if(input_just_pressed(KeyCode::P))
+                    .run_if(not(is_typing)),
+            )
+            .add_system(slot_button_react)
+            .add_system(slot_button_hover)
+            .add_system(text_input.after(slot_button_react))
+            .add_system(update_picker_ui.after(text_input));
+    }
+}
+
+pub type SlotId = u32;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SlotInfo {
+    pub id: SlotId,
+    pub name: String,
+}
+
+/// Index of all save slots, persisted under [`SLOTS_KEY`].
+///
+/// The save of each slot is stored under [`save_key`].
+#[derive(Resource, Serialize, Deserialize, Clone, Debug)]
+pub struct SaveSlots {
+    pub slots: Vec<SlotInfo>,
+    /// Slot currently played, saves go there.
+    pub current: SlotId,
+    next_id: SlotId,
+}
+
+impl SaveSlots {
+    /// Reads the slots index, creating it from the pre-slots save if needed.
+    pub fn load(pkv: &mut PkvStore) -> SaveSlots {
+        if let Ok(slots) = pkv.get::<SaveSlots>(SLOTS_KEY) {
+            if !slots.slots.is_empty() {
+                return slots;
+            }
+        }
+        let mut slots = SaveSlots {
+            slots: vec![],
+            current: 0,
+            next_id: 0,
+        };
+        let id = slots.create(pkv, "Main".to_string());
+        // The index may have been lost after the migration, don't overwrite a newer save.
+        let has_save = matches!(
+            pkv.get::<serde_json::Value>(save_key(id)),
+            Ok(save) if !save.is_null()
+        );
+        match pkv.get::<serde_json::Value>(LEGACY_SAVE_KEY) {
+            Ok(legacy_save) if !legacy_save.is_null() && !has_save => {
+                if let Err(err) = pkv.set(save_key(id), &legacy_save) {
+                    error!("could not move previous save to a slot: {}", err);
+                } else if let Err(err) = pkv.set(LEGACY_SAVE_KEY, &serde_json::Value::Null) {
+                    // Cleared like deleted slots, so a lost index can't bring it back.
+                    error!("could not delete previous save: {}", err);
+                }
+            }
+            _ => {}
+        }
+        slots
+    }
+
+    fn persist(&self, pkv: &mut PkvStore) {
+        if let Err(err) = pkv.set(SLOTS_KEY, self) {
+            error!("could not save slots: {}", err);
+        }
+    }
+
+    pub fn get(&self, id: SlotId) -> Option<&SlotInfo> {
+        self.slots.iter().find(|slot| slot.id == id)
+    }
+
+    pub fn set_current(&mut self, pkv: &mut PkvStore, id: SlotId) {
+        self.current = id;
+        self.persist(pkv);
+    }
+
+    /// Creates an empty slot, its first load will be the default map.
+    pub fn create(&mut self, pkv: &mut PkvStore, name: String) -> SlotId {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.slots.push(SlotInfo { id, name });
+        self.persist(pkv);
+        id
+    }
+
+    /// Creates a slot holding `save`, so loading it doesn't overwrite any other slot.
+    pub fn create_with_save(&mut self, pkv: &mut PkvStore, name: String, save: &Save) -> SlotId {
+        let id = self.create(pkv, name);
+        if let Err(err) = pkv.set(save_key(id), save) {
+            error!("could not write the save of slot {}: {}", id, err);
+        }
+        id
+    }
+
+    pub fn rename(&mut self, pkv: &mut PkvStore, id: SlotId, name: String) {
+        let Some(slot) = self.slots.iter_mut().find(|slot| slot.id == id) else {
+            return;
+        };
+        slot.name = name;
+        self.persist(pkv);
+    }
+
+    pub fn duplicate(&mut self, pkv: &mut PkvStore, id: SlotId) -> Option<SlotId> {
+        let name = format!("{} (copy)", self.get(id)?.name);
+        let new_id = self.create(pkv, name);
+        if let Ok(save) = pkv.get::<serde_json::Value>(save_key(id)) {
+            if let Err(err) = pkv.set(save_key(new_id), &save) {
+                error!("could not duplicate slot {}: {}", id, err);
+            }
+        }
+        Some(new_id)
+    }
+
+    /// Removes a slot and its save. The last slot can't be deleted.
+    pub fn delete(&mut self, pkv: &mut PkvStore, id: SlotId) -> bool {
+        if self.slots.len() <= 1 {
+            return false;
+        }
+        self.slots.retain(|slot| slot.id != id);
+        if self.current == id {
+            self.current = self.slots[0].id;
+        }
+        // A null save is loaded as the default map.
+        if let Err(err) = pkv.set(save_key(id), &serde_json::Value::Null) {
+            error!("could not delete save of slot {}: {}", id, err);
+        }
+        if let Err(err) = pkv.set(history_key(id), &SaveHistory::default()) {
+            error!("could not delete history of slot {}: {}", id, err);
+        }
+        self.persist(pkv);
+        true
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum TextInput {
+    Rename(SlotId),
+    /// Seed of a new game, any text is accepted, see [`parse_seed`].
+    NewSeed,
+}
+
+/// The current slot is loaded right away, the picker lets the player choose
+/// another one on startup, see [`load_current_slot`].
+#[derive(Resource, Default)]
+pub struct SlotPicker {
+    pub is_open: bool,
+    /// What is being typed, and the text typed so far.
+    pub typing: Option<(TextInput, String)>,
+}
+
+/// Keyboard shortcuts should be ignored while typing in the picker.
+pub fn is_typing(picker: Res<SlotPicker>) -> bool {
+    picker.typing.is_some()
+}
+
+/// Numbers are used as is, other texts are hashed (FNV-1a) so any word can be a seed.
+pub fn parse_seed(text: &str) -> u64 {
+    text.parse::<u64>().unwrap_or_else(|_| {
+        text.bytes().fold(0xcbf2_9ce4_8422_2325u64, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+        })
+    })
+}
+
+/// Opens the picker when there is more than one slot to choose from.
+fn load_current_slot(
+    slots: Res<SaveSlots>,
+    mut picker: ResMut<SlotPicker>,
+    mut event_load: EventWriter<EventLoad>,
+) {
+    event_load.send(EventLoad {
+        slot: slots.current,
+    });
+    picker.is_open = slots.slots.len() > 1;
+}
+
+fn toggle_picker(mut picker: ResMut<SlotPicker>) {
+    picker.is_open = !picker.is_open;
+}
+
+#[derive(Clone, Copy)]
+enum SlotAction {
+    Play(SlotId),
+    Rename(SlotId),
+    Duplicate(SlotId),
+    Delete(SlotId),
+    New,
+    NewWithSeed,
+    Close,
+}
+
+#[derive(Component)]
+struct SlotButton(SlotAction);
+
+#[derive(Component)]
+struct SlotPickerPanel;
+
+/// The played slot is saved before switching, so its progress since the last save is kept.
+fn slot_button_react(
+    snapshot: SaveSnapshot,
+    mut writer: SaveWriter,
+    mut slots: ResMut<SaveSlots>,
+    mut picker: ResMut<SlotPicker>,
+    mut event_load: EventWriter<EventLoad>,
+    q_buttons: Query<(&Interaction, &SlotButton), Changed<Interaction>>,
+) {
+    for (interaction, button) in q_buttons.iter() {
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+        match button.0 {
+            SlotAction::Play(id) => {
+                // Switches even if the played slot could not be saved.
+                let _ = writer.save(&snapshot, slots.current);
+                slots.set_current(writer.pkv(), id);
+                event_load.send(EventLoad { slot: id });
+                picker.is_open = false;
+            }
+            SlotAction::Rename(id) => {
+                let name = slots.get(id).map_or(String::new(), |s| s.name.clone());
+                picker.typing = Some((TextInput::Rename(id), name));
+            }
+            SlotAction::Duplicate(id) => {
+                slots.duplicate(writer.pkv(), id);
+            }
+            SlotAction::Delete(id) => {
+                let was_current = slots.current == id;
+                // The deleted map is not saved, saves wait for the next one to load.
+                if slots.delete(writer.pkv(), id) && was_current {
+                    event_load.send(EventLoad {
+                        slot: slots.current,
+                    });
+                }
+            }
+            SlotAction::New => {
+                let name = format!("Slot {}", slots.slots.len() + 1);
+                slots.create(writer.pkv(), name);
+            }
+            SlotAction::NewWithSeed => {
+                picker.typing = Some((TextInput::NewSeed, String::new()));
+            }
+            SlotAction::Close => {
+                picker.is_open = false;
+            }
+        }
+    }
+}
+
+fn slot_button_hover(
+    slots: Res<SaveSlots>,
+    mut q_buttons: Query<(&Interaction, &SlotButton, &mut BackgroundColor), Changed<Interaction>>,
+) {
+    for (interaction, button, mut color) in q_buttons.iter_mut() {
+        *color = match (interaction, button.0) {
+            (Interaction::Hovered, _) => BUTTON_HOVERED_COLOR.into(),
+            (_, SlotAction::Play(id)) if id == slots.current => CURRENT_SLOT_COLOR.into(),
+            _ => BUTTON_COLOR.into(),
+        };
+    }
+}
+
+fn text_input(
+    snapshot: SaveSnapshot,
+    mut writer: SaveWriter,
+    mut slots: ResMut<SaveSlots>,
+    mut picker: ResMut<SlotPicker>,
+    mut event_load: EventWriter<EventLoad>,
+    mut characters: EventReader<ReceivedCharacter>,
+    keys: Res<Input<KeyCode>>,
+) {
+    let Some((input, name)) = &mut picker.typing else {
+        characters.clear();
+        return;
+    };
+    let mut changed = false;
+    for c in characters.iter() {
+        if !c.char.is_control() {
+            name.push(c.char);
+            changed = true;
+        }
+    }
+    if keys.just_pressed(KeyCode::Back) {
+        name.pop();
+        changed = true;
+    }
+    if keys.just_pressed(KeyCode::Return) {
+        let (input, text) = (*input, name.trim().to_string());
+        picker.typing = None;
+        if text.is_empty() {
+            return;
+        }
+        match input {
+            TextInput::Rename(id) => slots.rename(writer.pkv(), id, text),
+            TextInput::NewSeed => {
+                let _ = writer.save(&snapshot, slots.current);
+                let save = default_save_with_seed(parse_seed(&text));
+                let id = slots.create_with_save(writer.pkv(), format!("Seed {}", text), &save);
+                slots.set_current(writer.pkv(), id);
+                event_load.send(EventLoad { slot: id });
+                picker.is_open = false;
+            }
+        }
+    } else if keys.just_pressed(KeyCode::Escape) {
+        picker.typing = None;
+    } else if changed {
+        // Trigger change detection to refresh the ui.
+        picker.set_changed();
+    }
+}
+
+fn update_picker_ui(
+    mut commands: Commands,
+    map_assets: Res<MapAssets>,
+    slots: Res<SaveSlots>,
+    picker: Res<SlotPicker>,
+    q_panel: Query<Entity, With<SlotPickerPanel>>,
+) {
+    if !slots.is_changed() && !picker.is_changed() {
+        return;
+    }
+    for panel in q_panel.iter() {
+        commands.entity(panel).despawn_recursive();
+    }
+    if !picker.is_open {
+        return;
+    }
+    let text_style = TextStyle {
+        color: Color::WHITE,
+        ..map_assets.text_style.clone()
+    };
+    let button = |parent: &mut ChildBuilder, label: &str, action: SlotAction| {
+        parent
+            .spawn((
+                ButtonBundle {
+                    style: Style {
+                        margin: UiRect::all(Val::Px(4.0)),
+                        padding: UiRect::all(Val::Px(6.0)),
+                        ..default()
+                    },
+                    background_color: match action {
+                        SlotAction::Play(id) if id == slots.current => CURRENT_SLOT_COLOR.into(),
+                        _ => BUTTON_COLOR.into(),
+                    },
+                    ..default()
+                },
+                SlotButton(action),
+            ))
+            .with_children(|parent| {
+                parent.spawn(TextBundle::from_section(label, text_style.clone()));
+            });
+    };
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        top: Val::Px(120.0),
+                        left: Val::Px(15.0),
+                        ..default()
+                    },
+                    flex_direction: FlexDirection::Column,
+                    padding: UiRect::all(Val::Px(10.0)),
+                    ..default()
+                },
+                background_color: Color::rgba(0.1, 0.1, 0.1, 0.9).into(),
+                ..default()
+            },
+            SlotPickerPanel,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "Save slots (P)",
+                text_style.clone(),
+            ));
+            for slot in slots.slots.iter() {
+                parent
+                    .spawn(NodeBundle {
+                        style: Style {
+                            flex_direction: FlexDirection::Row,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        ..default()
+                    })
+                    .with_children(|parent| {
+                        let name = match &picker.typing {
+                            Some((TextInput::Rename(id), name)) if *id == slot.id => {
+                                format!("{}_", name)
+                            }
+                            _ => slot.name.clone(),
+                        };
+                        button(parent, &name, SlotAction::Play(slot.id));
+                        button(parent, "Rename", SlotAction::Rename(slot.id));
+                        button(parent, "Copy", SlotAction::Duplicate(slot.id));
+                        if slots.slots.len() > 1 {
+                            button(parent, "Delete", SlotAction::Delete(slot.id));
+                        }
+                    });
+            }
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Row,
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    button(parent, "New slot", SlotAction::New);
+                    button(parent, "New from seed", SlotAction::NewWithSeed);
+                    button(parent, "Close", SlotAction::Close);
+                });
+            if let Some((TextInput::NewSeed, seed)) = &picker.typing {
+                parent.spawn(TextBundle::from_section(
+                    format!("Seed: {}_", seed),
+                    text_style.clone(),
+                ));
+            }
+        });
+}
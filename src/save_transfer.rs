@@ -0,0 +1,290 @@
+//! Export and import of saves as a portable text, to back up, share or report a board state.
+//!
+//! The text is `sidle.<base64 of the json save>.<crc32 of the json save>`.
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use bevy::{input::common_conditions::input_just_pressed, prelude::*};
+
+use crate::persisted_game::{self, Save, SaveSnapshot, SaveWriter};
+use crate::save_slots::{is_typing, SaveSlots};
+use crate::save_validation::{SaveProblem, SaveProblems};
+
+const EXPORT_PREFIX: &str = "sidle";
+
+pub struct SaveTransferPlugin;
+
+impl Plugin for SaveTransferPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(
+            export_save
+                .run_if(input_just_pressed(KeyCode::X))
+                .run_if(not(is_typing)),
+        )
+        .add_system(
+            import_save
+                .run_if(input_just_pressed(KeyCode::I))
+                .run_if(not(is_typing)),
+        );
+    }
+}
+
+#[derive(Debug)]
+pub enum ImportError {
+    BadFormat,
+    Base64(String),
+    Checksum {
+        expected: u32,
+        found: u32,
+    },
+    Json(String),
+    /// The save is well formed but couldn't be migrated or read.
+    Save(String),
+}
+
+impl std::fmt::Display for ImportError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ImportError::BadFormat => write!(f, "not an exported save"),
+            ImportError::Base64(err) => write!(f, "invalid base64: {}", err),
+            ImportError::Checksum { expected, found } => write!(
+                f,
+                "checksum mismatch, expected {:08x} but found {:08x}",
+                expected, found
+            ),
+            ImportError::Json(err) => write!(f, "invalid json: {}", err),
+            ImportError::Save(err) => write!(f, "invalid save: {}", err),
+        }
+    }
+}
+
+pub fn export_string(save: &Save) -> String {
+    let json = serde_json::to_string(save).expect("saves should be serializable.");
+    format!(
+        "{}.{}.{:08x}",
+        EXPORT_PREFIX,
+        URL_SAFE_NO_PAD.encode(json.as_bytes()),
+        crc32(json.as_bytes())
+    )
+}
+
+/// Reads a text from [`export_string`], or a plain json save.
+pub fn import_string(text: &str) -> Result<Save, ImportError> {
+    let text = text.trim();
+    let json = if text.starts_with('{') {
+        text.as_bytes().to_vec()
+    } else {
+        let mut parts = text.split('.');
+        let (Some(EXPORT_PREFIX), Some(data), Some(checksum), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err(ImportError::BadFormat);
+        };
+        let expected = u32::from_str_radix(checksum, 16).map_err(|_| ImportError::BadFormat)?;
+        let json = URL_SAFE_NO_PAD
+            .decode(data)
+            .map_err(|err| ImportError::Base64(err.to_string()))?;
+        let found = crc32(&json);
+        if found != expected {
+            return Err(ImportError::Checksum { expected, found });
+        }
+        json
+    };
+    let raw = serde_json::from_slice::<serde_json::Value>(&json)
+        .map_err(|err| ImportError::Json(err.to_string()))?;
+    persisted_game::parse_save(raw).map_err(ImportError::Save)
+}
+
+/// CRC-32 (IEEE), enough to catch truncated or mistyped exports.
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for byte in bytes {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+fn export_save(snapshot: SaveSnapshot, slots: Res<SaveSlots>, mut problems: ResMut<SaveProblems>) {
+    let (save, save_problems) = snapshot.snapshot();
+    problems.report("export", save_problems);
+    let text = export_string(&save);
+    info!("exported save: {}", text);
+    let name = slots
+        .get(slots.current)
+        .map_or("save".to_string(), |slot| slot.name.clone());
+    if let Err(err) = platform::write_export(&name, &save, &text) {
+        error!("could not export save: {}", err);
+    }
+}
+
+/// Imports into a new slot, the played one is saved first like when switching slots.
+fn import_save(
+    mut commands: Commands,
+    snapshot: SaveSnapshot,
+    mut writer: SaveWriter,
+    mut slots: ResMut<SaveSlots>,
+) {
+    let Some(text) = platform::read_import() else {
+        return;
+    };
+    match import_string(&text) {
+        Ok(save) => {
+            // Imports even if the played slot could not be saved.
+            let _ = writer.save(&snapshot, slots.current);
+            let id = slots.create_with_save(writer.pkv(), "Imported".to_string(), &save);
+            slots.set_current(writer.pkv(), id);
+            let load_problems = persisted_game::start_load(&mut commands, &save, id);
+            writer.problems().report("import", load_problems);
+        }
+        Err(err) => {
+            error!("could not import save: {}", err);
+            let reason = err.to_string();
+            writer
+                .problems()
+                .report("import", vec![SaveProblem::NotImported { reason }]);
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod platform {
+    use std::path::PathBuf;
+
+    use bevy::prelude::*;
+
+    use crate::persisted_game::Save;
+
+    const EXPORT_DIR: &str = "exports";
+    /// File read on import, it can contain an exported text or a json save.
+    const IMPORT_FILE: &str = "import.txt";
+
+    pub fn write_export(name: &str, save: &Save, text: &str) -> std::io::Result<()> {
+        std::fs::create_dir_all(EXPORT_DIR)?;
+        let name: String = name
+            .chars()
+            .map(|c| if c.is_alphanumeric() { c } else { '_' })
+            .collect();
+        let path = PathBuf::from(EXPORT_DIR).join(&name);
+        let json = serde_json::to_string_pretty(save).expect("saves should be serializable.");
+        std::fs::write(path.with_extension("json"), json)?;
+        std::fs::write(path.with_extension("txt"), text)?;
+        info!("save exported to {}.json", path.display());
+        Ok(())
+    }
+
+    pub fn read_import() -> Option<String> {
+        match std::fs::read_to_string(IMPORT_FILE) {
+            Ok(text) => Some(text),
+            Err(err) => {
+                error!("could not read {}: {}", IMPORT_FILE, err);
+                None
+            }
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+mod platform {
+    use crate::persisted_game::Save;
+
+    /// Shows the text in a prompt, so the player can copy it.
+    pub fn write_export(_name: &str, _save: &Save, text: &str) -> Result<(), String> {
+        let window = web_sys::window().ok_or("no window")?;
+        window
+            .prompt_with_message_and_default("Copy your save:", text)
+            .map(|_| ())
+            .map_err(|err| format!("{:?}", err))
+    }
+
+    pub fn read_import() -> Option<String> {
+        web_sys::window()?
+            .prompt_with_message("Paste a save:")
+            .ok()
+            .flatten()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::persisted_game::default_save_with_seed;
+
+    #[test]
+    fn export_round_trip() {
+        let mut save = default_save_with_seed(5);
+        // Json doesn't keep the last digit of every float.
+        save.last_tick_time_since2023 = 1000.5;
+        let text = export_string(&save);
+        assert!(text.starts_with("sidle."));
+        let imported = import_string(&format!("  {}\n", text)).unwrap();
+        assert_eq!(
+            serde_json::to_value(&imported).unwrap(),
+            serde_json::to_value(&save).unwrap()
+        );
+    }
+
+    #[test]
+    fn plain_json_is_imported() {
+        let save = default_save_with_seed(5);
+        let imported = import_string(&serde_json::to_string(&save).unwrap()).unwrap();
+        assert_eq!(imported.map_rng.seed, 5);
+    }
+
+    #[test]
+    fn corrupted_checksum_is_rejected() {
+        let text = export_string(&default_save_with_seed(5));
+        let (data, checksum) = text.rsplit_once('.').unwrap();
+        let checksum = u32::from_str_radix(checksum, 16).unwrap();
+        let corrupted = format!("{}.{:08x}", data, checksum ^ 1);
+        assert!(matches!(
+            import_string(&corrupted),
+            Err(ImportError::Checksum { expected, found })
+                if expected == checksum ^ 1 && found == checksum
+        ));
+    }
+
+    #[test]
+    fn bad_format_is_rejected() {
+        let text = export_string(&default_save_with_seed(5));
+        assert!(matches!(
+            import_string(&text.replacen("sidle", "other", 1)),
+            Err(ImportError::BadFormat)
+        ));
+        assert!(matches!(
+            import_string("sidle.e30"),
+            Err(ImportError::BadFormat)
+        ));
+        assert!(matches!(
+            import_string(&format!("{}.extra", text)),
+            Err(ImportError::BadFormat)
+        ));
+        assert!(matches!(
+            import_string("sidle.not base64!.00000000"),
+            Err(ImportError::Base64(_))
+        ));
+    }
+
+    #[test]
+    fn truncated_export_is_rejected() {
+        let text = export_string(&default_save_with_seed(5));
+        let (data, checksum) = text.rsplit_once('.').unwrap();
+        let truncated = format!("{}.{}", &data[..data.len() - 10], checksum);
+        assert!(matches!(
+            import_string(&truncated),
+            Err(ImportError::Base64(_)) | Err(ImportError::Checksum { .. })
+        ));
+    }
+
+    #[test]
+    fn crc32_matches_the_standard() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(
+            crc32(b"The quick brown fox jumps over the lazy dog"),
+            0x414F_A339
+        );
+    }
+}
@@ -0,0 +1,467 @@
+use bevy::prelude::*;
+
+use crate::persisted_game::{Save, SavedNode};
+use crate::save_slots::SlotId;
+
+/// Problems listed on screen, the others are only counted.
+const MAX_PROBLEMS_SHOWN: usize = 5;
+const PROBLEMS_DISPLAY_SECONDS: f32 = 10f32;
+
+pub struct SaveValidationPlugin;
+
+impl Plugin for SaveValidationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SaveProblems>()
+            .add_startup_system(setup_problems_text)
+            .add_system(show_save_problems);
+    }
+}
+
+/// Problems found in the last saved or loaded game, they were repaired or dropped.
+///
+/// Shown to the player for a while, as a repaired save may not be what they expect,
+/// and a save that couldn't be written means progress may be lost.
+#[derive(Resource, Default, Debug)]
+pub struct SaveProblems {
+    /// What found the problems, like "load".
+    pub context: String,
+    pub problems: Vec<SaveProblem>,
+}
+
+impl SaveProblems {
+    pub fn report(&mut self, context: &str, problems: Vec<SaveProblem>) {
+        for problem in problems.iter() {
+            warn!("{}: {}", context, problem);
+        }
+        self.context = context.to_string();
+        self.problems = problems;
+    }
+
+    fn describe(&self) -> String {
+        let mut lines = vec![format!(
+            "{} problems found on {}:",
+            self.problems.len(),
+            self.context
+        )];
+        lines.extend(
+            self.problems
+                .iter()
+                .take(MAX_PROBLEMS_SHOWN)
+                .map(|problem| format!("- {}", problem)),
+        );
+        if self.problems.len() > MAX_PROBLEMS_SHOWN {
+            lines.push(format!(
+                "and {} more",
+                self.problems.len() - MAX_PROBLEMS_SHOWN
+            ));
+        }
+        lines.join("\n")
+    }
+}
+
+/// Hides the problems once finished.
+#[derive(Component, Default)]
+struct SaveProblemsText(Timer);
+
+fn setup_problems_text(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                font_size: 20.0,
+                color: Color::ORANGE,
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                top: Val::Px(5.0),
+                left: Val::Percent(35.0),
+                ..default()
+            },
+            ..default()
+        }),
+        SaveProblemsText::default(),
+    ));
+}
+
+fn show_save_problems(
+    time: Res<Time>,
+    problems: Res<SaveProblems>,
+    mut q_text: Query<(&mut Text, &mut SaveProblemsText)>,
+) {
+    for (mut text, mut shown) in q_text.iter_mut() {
+        if problems.is_changed() && !problems.problems.is_empty() {
+            text.sections[0].value = problems.describe();
+            shown.0 = Timer::from_seconds(PROBLEMS_DISPLAY_SECONDS, TimerMode::Once);
+        } else if shown.0.tick(time.delta()).just_finished() {
+            text.sections[0].value.clear();
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SaveProblem {
+    /// An entity referenced by a node is not a node anymore.
+    DanglingEntity {
+        entity: Entity,
+    },
+    /// A node refers to a node index which doesn't exist.
+    MissingNode {
+        node: usize,
+        reference: usize,
+    },
+    /// A node was removed before it could be loaded.
+    MissingLoadingNode {
+        node: usize,
+    },
+    SelfReference {
+        node: usize,
+    },
+    DuplicateReference {
+        node: usize,
+        reference: usize,
+    },
+    /// `blocker` blocks `node`, but only one of them knew about it.
+    OneSidedLink {
+        blocker: usize,
+        node: usize,
+    },
+    /// Blocking `node` would make `blocker` block itself.
+    Cycle {
+        blocker: usize,
+        node: usize,
+    },
+    InvalidTimer {
+        node: usize,
+    },
+    InvalidPosition {
+        node: usize,
+    },
+    /// Storage refused the save, like a full or denied web storage.
+    NotWritten {
+        slot: SlotId,
+        reason: String,
+    },
+    /// The save of `slot` couldn't be loaded, a new map was started instead.
+    NotLoaded {
+        slot: SlotId,
+        reason: String,
+    },
+    /// An imported text is not a readable save, nothing was imported.
+    NotImported {
+        reason: String,
+    },
+}
+
+impl std::fmt::Display for SaveProblem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SaveProblem::DanglingEntity { entity } => {
+                write!(f, "dropped reference to removed entity {:?}", entity)
+            }
+            SaveProblem::MissingNode { node, reference } => write!(
+                f,
+                "node {} refers to missing node {}, dropped",
+                node, reference
+            ),
+            SaveProblem::MissingLoadingNode { node } => {
+                write!(f, "node {} disappeared while loading, skipped", node)
+            }
+            SaveProblem::SelfReference { node } => {
+                write!(f, "node {} refers to itself, dropped", node)
+            }
+            SaveProblem::DuplicateReference { node, reference } => write!(
+                f,
+                "node {} refers to node {} more than once, deduplicated",
+                node, reference
+            ),
+            SaveProblem::OneSidedLink { blocker, node } => write!(
+                f,
+                "link from blocker {} to node {} was one-sided, completed",
+                blocker, node
+            ),
+            SaveProblem::Cycle { blocker, node } => write!(
+                f,
+                "link from blocker {} to node {} makes a cycle, dropped",
+                blocker, node
+            ),
+            SaveProblem::InvalidTimer { node } => {
+                write!(f, "node {} has an invalid timer, clamped", node)
+            }
+            SaveProblem::InvalidPosition { node } => {
+                write!(f, "node {} has an invalid position, moved to origin", node)
+            }
+            SaveProblem::NotWritten { slot, reason } => write!(
+                f,
+                "slot {} could not be written, progress is not saved: {}",
+                slot, reason
+            ),
+            SaveProblem::NotLoaded { slot, reason } => write!(
+                f,
+                "slot {} could not be loaded, started a new map: {}",
+                slot, reason
+            ),
+            SaveProblem::NotImported { reason } => {
+                write!(f, "could not import save: {}", reason)
+            }
+        }
+    }
+}
+
+/// Repairs `save` so it can be loaded safely, returning what was wrong.
+pub fn validate(save: &mut Save) -> Vec<SaveProblem> {
+    let mut problems = Vec::new();
+    let len = save.nodes.len();
+    for (i, node) in save.nodes.iter_mut().enumerate() {
+        if !node.pos.is_finite() {
+            problems.push(SaveProblem::InvalidPosition { node: i });
+            node.pos = Vec2::ZERO;
+        }
+        if !node.timer_seconds_duration.is_finite() || node.timer_seconds_duration < 0f32 {
+            problems.push(SaveProblem::InvalidTimer { node: i });
+            node.timer_seconds_duration = 0f32;
+        }
+        if !(0f32..=node.timer_seconds_duration).contains(&node.timer_seconds_left) {
+            problems.push(SaveProblem::InvalidTimer { node: i });
+            node.timer_seconds_left = if node.timer_seconds_left.is_finite() {
+                node.timer_seconds_left
+                    .clamp(0f32, node.timer_seconds_duration)
+            } else {
+                node.timer_seconds_duration
+            };
+        }
+        check_references(i, &mut node.to_block, len, &mut problems);
+        check_references(i, &mut node.blockers, len, &mut problems);
+    }
+    complete_links(&mut save.nodes, &mut problems);
+    let mut visit = vec![Visit::New; len];
+    for i in 0..len {
+        break_cycles(&mut save.nodes, i, &mut visit, &mut problems);
+    }
+    problems
+}
+
+fn check_references(
+    node: usize,
+    references: &mut Vec<usize>,
+    len: usize,
+    problems: &mut Vec<SaveProblem>,
+) {
+    let mut seen = Vec::with_capacity(references.len());
+    references.retain(|reference| {
+        let problem = if *reference >= len {
+            SaveProblem::MissingNode {
+                node,
+                reference: *reference,
+            }
+        } else if *reference == node {
+            SaveProblem::SelfReference { node }
+        } else if seen.contains(reference) {
+            SaveProblem::DuplicateReference {
+                node,
+                reference: *reference,
+            }
+        } else {
+            seen.push(*reference);
+            return true;
+        };
+        problems.push(problem);
+        false
+    });
+}
+
+/// Makes sure `to_block` and `blockers` mirror each other.
+fn complete_links(nodes: &mut [SavedNode], problems: &mut Vec<SaveProblem>) {
+    for i in 0..nodes.len() {
+        for blocker in nodes[i].blockers.clone() {
+            if !nodes[blocker].to_block.contains(&i) {
+                problems.push(SaveProblem::OneSidedLink { blocker, node: i });
+                nodes[blocker].to_block.push(i);
+            }
+        }
+        for to_block in nodes[i].to_block.clone() {
+            if !nodes[to_block].blockers.contains(&i) {
+                problems.push(SaveProblem::OneSidedLink {
+                    blocker: i,
+                    node: to_block,
+                });
+                nodes[to_block].blockers.push(i);
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Visit {
+    New,
+    InProgress,
+    Done,
+}
+
+/// Block propagation recurses through `to_block`, so it must not loop.
+fn break_cycles(
+    nodes: &mut [SavedNode],
+    i: usize,
+    visit: &mut [Visit],
+    problems: &mut Vec<SaveProblem>,
+) {
+    if visit[i] != Visit::New {
+        return;
+    }
+    visit[i] = Visit::InProgress;
+    for to_block in nodes[i].to_block.clone() {
+        if visit[to_block] == Visit::InProgress {
+            problems.push(SaveProblem::Cycle {
+                blocker: i,
+                node: to_block,
+            });
+            nodes[i].to_block.retain(|n| *n != to_block);
+            nodes[to_block].blockers.retain(|n| *n != i);
+        } else {
+            break_cycles(nodes, to_block, visit, problems);
+        }
+    }
+    visit[i] = Visit::Done;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::persisted_game::default_save_with_seed;
+    use crate::persisted_game::test_saves::{gain, save_with};
+
+    fn node(to_block: Vec<usize>, blockers: Vec<usize>) -> SavedNode {
+        SavedNode {
+            to_block,
+            blockers,
+            ..gain(2f32, 1f32)
+        }
+    }
+
+    #[test]
+    fn valid_save_is_unchanged() {
+        let mut save = default_save_with_seed(0);
+        assert_eq!(validate(&mut save), vec![]);
+        assert_eq!(save.nodes[0].blockers, vec![1]);
+        assert_eq!(save.nodes[1].to_block, vec![0]);
+    }
+
+    #[test]
+    fn missing_blocker_is_dropped() {
+        let mut save = save_with(vec![node(vec![], vec![5])]);
+        let problems = validate(&mut save);
+        assert_eq!(
+            problems,
+            vec![SaveProblem::MissingNode {
+                node: 0,
+                reference: 5
+            }]
+        );
+        assert!(save.nodes[0].blockers.is_empty());
+    }
+
+    #[test]
+    fn self_reference_is_dropped() {
+        let mut save = save_with(vec![node(vec![0], vec![])]);
+        let problems = validate(&mut save);
+        assert_eq!(problems, vec![SaveProblem::SelfReference { node: 0 }]);
+        assert!(save.nodes[0].to_block.is_empty());
+    }
+
+    #[test]
+    fn duplicate_references_are_deduplicated() {
+        let mut save = save_with(vec![node(vec![1, 1], vec![]), node(vec![], vec![0])]);
+        let problems = validate(&mut save);
+        assert_eq!(
+            problems,
+            vec![SaveProblem::DuplicateReference {
+                node: 0,
+                reference: 1
+            }]
+        );
+        assert_eq!(save.nodes[0].to_block, vec![1]);
+        assert_eq!(save.nodes[1].blockers, vec![0]);
+    }
+
+    #[test]
+    fn one_sided_links_are_completed() {
+        let mut only_to_block = save_with(vec![node(vec![1], vec![]), node(vec![], vec![])]);
+        let problems = validate(&mut only_to_block);
+        assert_eq!(
+            problems,
+            vec![SaveProblem::OneSidedLink {
+                blocker: 0,
+                node: 1
+            }]
+        );
+        assert_eq!(only_to_block.nodes[1].blockers, vec![0]);
+
+        let mut only_blockers = save_with(vec![node(vec![], vec![]), node(vec![], vec![0])]);
+        let problems = validate(&mut only_blockers);
+        assert_eq!(
+            problems,
+            vec![SaveProblem::OneSidedLink {
+                blocker: 0,
+                node: 1
+            }]
+        );
+        assert_eq!(only_blockers.nodes[0].to_block, vec![1]);
+    }
+
+    #[test]
+    fn blocking_cycle_is_broken() {
+        let mut save = save_with(vec![node(vec![1], vec![1]), node(vec![0], vec![0])]);
+        let problems = validate(&mut save);
+        assert_eq!(
+            problems,
+            vec![SaveProblem::Cycle {
+                blocker: 1,
+                node: 0
+            }]
+        );
+        assert_eq!(save.nodes[0].to_block, vec![1]);
+        assert!(save.nodes[0].blockers.is_empty());
+        assert!(save.nodes[1].to_block.is_empty());
+        assert_eq!(save.nodes[1].blockers, vec![0]);
+    }
+
+    #[test]
+    fn invalid_timers_are_clamped() {
+        let mut negative = node(vec![], vec![]);
+        negative.timer_seconds_left = -1f32;
+        let mut nan = node(vec![], vec![]);
+        nan.timer_seconds_left = f32::NAN;
+        let mut save = save_with(vec![negative, nan]);
+        let problems = validate(&mut save);
+        assert_eq!(
+            problems,
+            vec![
+                SaveProblem::InvalidTimer { node: 0 },
+                SaveProblem::InvalidTimer { node: 1 }
+            ]
+        );
+        assert_eq!(save.nodes[0].timer_seconds_left, 0f32);
+        // A timer left unknown restarts.
+        assert_eq!(save.nodes[1].timer_seconds_left, 2f32);
+    }
+
+    #[test]
+    fn negative_duration_is_reset() {
+        let mut negative = node(vec![], vec![]);
+        negative.timer_seconds_duration = -2f32;
+        let mut save = save_with(vec![negative]);
+        let problems = validate(&mut save);
+        // The time left doesn't fit the repaired duration anymore.
+        assert_eq!(
+            problems,
+            vec![
+                SaveProblem::InvalidTimer { node: 0 },
+                SaveProblem::InvalidTimer { node: 0 }
+            ]
+        );
+        assert_eq!(save.nodes[0].timer_seconds_duration, 0f32);
+        assert_eq!(save.nodes[0].timer_seconds_left, 0f32);
+    }
+}
@@ -0,0 +1,40 @@
+use bevy::prelude::*;
+use bevy_pkv::PkvStore;
+use serde::{Deserialize, Serialize};
+
+use crate::number_format::Notation;
+
+const SETTINGS_KEY: &str = "settings";
+
+/// Player preferences, shared by all save slots.
+#[derive(Resource, Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct Settings {
+    /// 0 disables periodic autosave, saving on exit still happens.
+    pub autosave_interval_seconds: f32,
+    pub notation: Notation,
+    /// Whether auto-clickers bought in the shop click, toggled with C.
+    pub automation_enabled: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            autosave_interval_seconds: 30f32,
+            notation: Notation::default(),
+            automation_enabled: true,
+        }
+    }
+}
+
+impl Settings {
+    pub fn load(pkv: &PkvStore) -> Settings {
+        pkv.get::<Settings>(SETTINGS_KEY).unwrap_or_default()
+    }
+
+    pub fn persist(&self, pkv: &mut PkvStore) {
+        if let Err(err) = pkv.set(SETTINGS_KEY, self) {
+            error!("could not save settings: {}", err);
+        }
+    }
+}
@@ -0,0 +1,701 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::automation::{
+    auto_click_interval, is_ready_for_auto_click, AutoClicker, NodeRule, RuleCondition, RuleFacts,
+};
+use crate::balance::Balance;
+use crate::bank::{advance_saved_bank, NodeBank};
+use crate::big_number::BigNumber;
+use crate::converter::{ConverterTarget, NodeConverter};
+use crate::gate::NodeGate;
+use crate::idle_gains::{Ledger, ResourceKind};
+use crate::multiplier::{consume_multipliers, NodeMultiplier};
+use crate::new_node::{pick_new_node, RandomForMap};
+use crate::offline_progress::{compute_inherited_block_status, manual_block, saved_tick_rate};
+use crate::overclock::NodeOverclock;
+use crate::persisted_game::{default_save_with_seed, NodeType, Save, SavedNode};
+use crate::prestige::Prestige;
+use crate::progress::NodeTimer;
+use crate::spawn_table::{SpawnContext, SpawnNode, SpawnTable};
+use crate::upgrade_shop::{accepts_rule, buy_upgrade, NodeUpgrades, Upgrade};
+use crate::{NodeCurrencyGain, NodeManualBlockToggle, NodeSave};
+
+/// Steps the [`Simulation`] resource on [`FixedTime`], for a `MinimalPlugins` app.
+pub struct SimulationPlugin;
+
+impl Plugin for SimulationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SimulationClick>()
+            .add_system(step_simulation.in_schedule(CoreSchedule::FixedUpdate));
+    }
+}
+
+/// Clicks the node at this index of [`Simulation::nodes`] before the next step.
+pub struct SimulationClick(pub usize);
+
+fn step_simulation(
+    fixed_time: Res<FixedTime>,
+    simulation: Option<ResMut<Simulation>>,
+    mut clicks: EventReader<SimulationClick>,
+) {
+    let Some(mut simulation) = simulation else {
+        return;
+    };
+    for click in clicks.iter() {
+        simulation.click(click.0);
+    }
+    simulation.step(fixed_time.period.as_secs_f32());
+}
+
+/// The node graph rules on plain data: no rendering, input or wall-clock time.
+///
+/// Clicks, timers, automation and spawning call the same rules as the game systems,
+/// on [`SavedNode`]s instead of entities. Nodes only spawn from the seeded map random,
+/// so a seed played with the same steps and clicks always gives the same map.
+#[derive(Resource)]
+pub struct Simulation {
+    save: Save,
+    random: RandomForMap,
+    balance: Balance,
+    spawn_table: SpawnTable,
+    /// By node index, like the [`AutoClicker`] component they are not saved.
+    auto_clickers: Vec<AutoClicker>,
+    elapsed_seconds: f64,
+}
+
+impl Simulation {
+    /// A new game generated from `seed`.
+    pub fn new(seed: u64, balance: Balance, spawn_table: SpawnTable) -> Self {
+        Self::from_save(default_save_with_seed(seed), balance, spawn_table)
+    }
+
+    pub fn from_save(save: Save, balance: Balance, spawn_table: SpawnTable) -> Self {
+        Self {
+            random: RandomForMap::from_saved(&save.map_rng),
+            save,
+            balance,
+            spawn_table,
+            auto_clickers: Vec::new(),
+            elapsed_seconds: 0f64,
+        }
+    }
+
+    /// The simulated game, as the game would save it.
+    pub fn to_save(&self) -> Save {
+        let mut save = self.save.clone();
+        save.map_rng = self.random.to_saved();
+        save.last_tick_time_since2023 += self.elapsed_seconds;
+        save
+    }
+
+    pub fn nodes(&self) -> &[SavedNode] {
+        &self.save.nodes
+    }
+
+    pub fn ledger(&self) -> &Ledger {
+        &self.save.resources
+    }
+
+    pub fn prestige(&self) -> &Prestige {
+        &self.save.prestige
+    }
+
+    /// Simulated seconds since the simulation was created.
+    pub fn elapsed_seconds(&self) -> f64 {
+        self.elapsed_seconds
+    }
+
+    /// Whether clicking the node at `index` would do something.
+    pub fn is_clickable(&self, index: usize) -> bool {
+        let Some(node) = self.save.nodes.get(index) else {
+            return false;
+        };
+        can_click(
+            node,
+            compute_inherited_block_status(&self.save.nodes)[index],
+        )
+    }
+
+    /// Indices of all the nodes [`Simulation::is_clickable`].
+    pub fn clickable_nodes(&self) -> Vec<usize> {
+        let inherited = compute_inherited_block_status(&self.save.nodes);
+        self.save
+            .nodes
+            .iter()
+            .zip(inherited)
+            .enumerate()
+            .filter(|(_, (node, inherited))| can_click(node, *inherited))
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// Clicks the node at `index`, returns false if it was not ready or blocked.
+    pub fn click(&mut self, index: usize) -> bool {
+        if !self.is_clickable(index) {
+            return false;
+        }
+        let coins = self.save.resources.get(ResourceKind::Coins);
+        match self.save.nodes[index].node_type {
+            NodeType::Gain { level, resource } => {
+                let boost = self.consume_multipliers(index);
+                let node = &mut self.save.nodes[index];
+                let upgrades = node.upgrades.clone();
+                let mut gain = NodeCurrencyGain { level, resource };
+                let gained = gain.click(
+                    node,
+                    &upgrades,
+                    boost,
+                    &mut self.save.resources,
+                    &self.save.prestige,
+                    &self.balance,
+                );
+                self.save.prestige.track(resource, gained);
+                node.node_type = NodeType::Gain {
+                    level: gain.level,
+                    resource,
+                };
+                self.reset_manual_blockers(index);
+                self.spawn_from(index, self.save.resources.get(ResourceKind::Coins));
+            }
+            NodeType::Save { level } => {
+                let node = &mut self.save.nodes[index];
+                let upgrades = node.upgrades.clone();
+                let mut save = NodeSave { level };
+                save.click(node, &upgrades, coins, &self.balance);
+                node.node_type = NodeType::Save { level: save.level };
+            }
+            NodeType::Blocker { is_blocked } => {
+                self.save.nodes[index].node_type = NodeType::Blocker {
+                    is_blocked: !is_blocked,
+                };
+            }
+            NodeType::Multiplier { .. } => return false,
+            NodeType::Converter { level, output } => {
+                return self.convert(index, NodeConverter { level, output });
+            }
+            NodeType::Gate { kind } => {
+                let node = &mut self.save.nodes[index];
+                let upgrades = node.upgrades.clone();
+                let mut gate = NodeGate { kind };
+                gate.click(node, &upgrades, &self.balance);
+                node.node_type = NodeType::Gate { kind: gate.kind };
+            }
+            NodeType::Bank {
+                level,
+                deposit,
+                rate,
+                cycles,
+            } => {
+                let node = &mut self.save.nodes[index];
+                let upgrades = node.upgrades.clone();
+                let mut bank = NodeBank {
+                    level,
+                    deposit,
+                    rate,
+                    cycles,
+                };
+                if !bank.click(node, &upgrades, &mut self.save.resources, &self.balance) {
+                    return false;
+                }
+                node.node_type = NodeType::Bank {
+                    level: bank.level,
+                    deposit: bank.deposit,
+                    rate: bank.rate,
+                    cycles: bank.cycles,
+                };
+            }
+            NodeType::Overclock { level, scope } => {
+                let node = &mut self.save.nodes[index];
+                let upgrades = node.upgrades.clone();
+                let mut overclock = NodeOverclock { level, scope };
+                overclock.click(
+                    node.pos,
+                    node,
+                    &upgrades,
+                    &mut self.save.time_buffs,
+                    &self.balance,
+                );
+                node.node_type = NodeType::Overclock {
+                    level: overclock.level,
+                    scope,
+                };
+            }
+        }
+        true
+    }
+
+    /// Buys `upgrade` for the node at `index` like the shop, false when not offered or too expensive.
+    pub fn buy_upgrade(&mut self, index: usize, upgrade: Upgrade) -> bool {
+        let Some(node) = self.save.nodes.get_mut(index) else {
+            return false;
+        };
+        let node_type = node.node_type.clone();
+        let mut upgrades = node.upgrades.clone();
+        let is_bought = buy_upgrade(
+            &node_type,
+            &mut upgrades,
+            node,
+            upgrade,
+            &mut self.save.resources,
+            &self.balance,
+        );
+        node.upgrades = upgrades;
+        is_bought
+    }
+
+    /// Sets the rule of the node at `index` like the shop, false when the node takes no rule.
+    pub fn set_rule(&mut self, index: usize, condition: Option<RuleCondition>) -> bool {
+        let Some(node) = self.save.nodes.get_mut(index) else {
+            return false;
+        };
+        if !accepts_rule(&node.node_type) {
+            return false;
+        }
+        node.rule.condition = condition;
+        true
+    }
+
+    /// Runs automation, then advances all timers by `delta_seconds`,
+    /// finished blockers block and spawn a node.
+    pub fn step(&mut self, delta_seconds: f32) {
+        self.run_automation(delta_seconds);
+        let inherited = compute_inherited_block_status(&self.save.nodes);
+        let mut finished_blockers = Vec::new();
+        for (index, (node, inherited)) in self.save.nodes.iter_mut().zip(inherited).enumerate() {
+            let time_scale = self.save.time_buffs.time_scale_at(node.pos);
+            let progress =
+                delta_seconds * saved_tick_rate(node, inherited, &self.balance) * time_scale;
+            if let NodeType::Bank { .. } = node.node_type {
+                advance_saved_bank(node, progress, &self.balance);
+                continue;
+            }
+            if node.timer_seconds_left <= 0f32 {
+                continue;
+            }
+            node.timer_seconds_left -= progress;
+            if node.timer_seconds_left > 0f32 {
+                continue;
+            }
+            node.timer_seconds_left = 0f32;
+            if let NodeType::Blocker { .. } = node.node_type {
+                // Like `update_progress_manual_auto_block`.
+                node.node_type = NodeType::Blocker { is_blocked: true };
+                finished_blockers.push(index);
+            }
+        }
+        self.save.time_buffs.tick(delta_seconds);
+        let coins = self.save.resources.get(ResourceKind::Coins);
+        for index in finished_blockers {
+            self.spawn_from(index, coins);
+        }
+        self.elapsed_seconds += delta_seconds as f64;
+    }
+
+    /// Clicks what `run_auto_clickers` and `run_node_rules` would, automation being enabled.
+    fn run_automation(&mut self, delta_seconds: f32) {
+        let nodes = &self.save.nodes;
+        let inherited = compute_inherited_block_status(nodes);
+        self.auto_clickers
+            .resize_with(nodes.len(), AutoClicker::default);
+        let gains = nodes
+            .iter()
+            .zip(inherited.iter())
+            .filter(|(node, _)| matches!(node.node_type, NodeType::Gain { .. }))
+            .map(|(node, inherited)| (*inherited, node.is_finished()));
+        let facts = RuleFacts::new(self.save.resources.get(ResourceKind::Coins), gains);
+        let delta = Duration::from_secs_f32(delta_seconds);
+        let mut clicks = Vec::new();
+        for (index, (node, inherited)) in nodes.iter().zip(inherited).enumerate() {
+            // Overclocks are never automated, even by a rule from an older save.
+            if let NodeType::Overclock { .. } = node.node_type {
+                continue;
+            }
+            let manual = manual_block(node);
+            if let Some(condition) = node.rule.condition {
+                let self_blocked = manual.map(|manual| manual.is_self_blocked(node));
+                if node.is_finished() && !inherited && condition.wants_click(&facts, self_blocked) {
+                    clicks.push(index);
+                }
+                continue;
+            }
+            let Some(interval) = auto_click_interval(&node.upgrades, &node.rule) else {
+                continue;
+            };
+            let is_ready = is_ready_for_auto_click(
+                node.is_finished(),
+                inherited,
+                manual.map(|manual| manual.is_blocked),
+            );
+            if self.auto_clickers[index].tick(delta, interval, is_ready) {
+                clicks.push(index);
+            }
+        }
+        for index in clicks {
+            self.click(index);
+        }
+    }
+
+    /// False when coins are missing or nothing is targeted.
+    fn convert(&mut self, index: usize, mut converter: NodeConverter) -> bool {
+        if !converter.can_afford(&self.save.resources, &self.balance) {
+            return false;
+        }
+        let inherited = compute_inherited_block_status(&self.save.nodes);
+        // Like the queries of `node_converter_react`.
+        let candidates = self
+            .save
+            .nodes
+            .iter()
+            .zip(inherited)
+            .enumerate()
+            .filter(|(_, (node, _))| {
+                !matches!(
+                    node.node_type,
+                    NodeType::Blocker { .. } | NodeType::Converter { .. }
+                )
+            })
+            .map(|(index, (node, inherited))| {
+                let target = ConverterTarget {
+                    pos: node.pos,
+                    is_blocked: inherited,
+                    is_finished: node.is_finished(),
+                    is_gain: matches!(node.node_type, NodeType::Gain { .. }),
+                };
+                (index, target)
+            });
+        let Some(target) = converter.pick_target(self.save.nodes[index].pos, candidates) else {
+            return false;
+        };
+        if let Some(target) = target {
+            let node = &mut self.save.nodes[target];
+            let mut upgrades = node.upgrades.clone();
+            converter.output.apply(node, &mut upgrades);
+            node.upgrades = upgrades;
+        }
+        let node = &mut self.save.nodes[index];
+        let upgrades = node.upgrades.clone();
+        if let Some((resource, amount)) =
+            converter.convert(node, &upgrades, &mut self.save.resources, &self.balance)
+        {
+            self.save.prestige.track(resource, amount);
+        }
+        node.node_type = NodeType::Converter {
+            level: converter.level,
+            output: converter.output,
+        };
+        true
+    }
+
+    /// Charged multipliers reaching the gain at `gain_index`, see [`consume_multipliers`].
+    fn consume_multipliers(&mut self, gain_index: usize) -> BigNumber {
+        let gain_pos = self.save.nodes[gain_index].pos;
+        let inherited = compute_inherited_block_status(&self.save.nodes);
+        let multipliers =
+            self.save
+                .nodes
+                .iter_mut()
+                .zip(inherited)
+                .filter_map(|(node, inherited)| {
+                    let NodeType::Multiplier { level } = node.node_type else {
+                        return None;
+                    };
+                    let duration_factor = node.upgrades.duration_factor();
+                    Some((
+                        node.pos,
+                        NodeMultiplier { level },
+                        duration_factor,
+                        inherited,
+                        node,
+                    ))
+                });
+        consume_multipliers(gain_pos, multipliers, &self.balance)
+    }
+
+    /// Like `recurse_reset_manual`, blockers of a node are blocked again.
+    fn reset_manual_blockers(&mut self, index: usize) {
+        let Some(node) = self.save.nodes.get_mut(index) else {
+            return;
+        };
+        if let NodeType::Blocker { is_blocked } = node.node_type {
+            let upgrades = node.upgrades.clone();
+            let mut manual = NodeManualBlockToggle { is_blocked };
+            manual.reset(node, &upgrades, &self.balance);
+            node.node_type = NodeType::Blocker {
+                is_blocked: manual.is_blocked,
+            };
+        }
+        for blocker in node.blockers.clone() {
+            self.reset_manual_blockers(blocker);
+        }
+    }
+
+    /// A node may spawn next to the node at `from`, see [`pick_new_node`].
+    ///
+    /// Wired like in `new_button`.
+    fn spawn_from(&mut self, from: usize, currencies_on_click: BigNumber) {
+        let nodes = &mut self.save.nodes;
+        let existing_points: Vec<_> = nodes.iter().map(|node| (node.pos.x, node.pos.y)).collect();
+        let existing_nodes: Vec<_> = nodes
+            .iter()
+            .map(|node| SpawnNode::of(&node.node_type))
+            .collect();
+        let context = SpawnContext {
+            currencies_on_click,
+            prestige_points: self.save.prestige.points,
+            nodes: &existing_nodes,
+            source_blockers: nodes[from].blockers.len(),
+        };
+        let Some((pos, node_type, duration)) = pick_new_node(
+            &existing_points,
+            nodes[from].pos,
+            &context,
+            &self.spawn_table,
+            &mut self.random.random,
+            &self.balance,
+            &self.save.prestige,
+        ) else {
+            return;
+        };
+        let is_blocker = matches!(node_type, NodeType::Blocker { .. });
+        let is_gate = matches!(node_type, NodeType::Gate { .. });
+        let index = nodes.len();
+        // The blockers of the node a gate spawned from become its inputs.
+        let inputs = if is_gate {
+            std::mem::replace(&mut nodes[from].blockers, vec![index])
+        } else {
+            vec![]
+        };
+        for input in inputs.iter() {
+            for target in nodes[*input].to_block.iter_mut() {
+                if *target == from {
+                    *target = index;
+                }
+            }
+        }
+        nodes.push(SavedNode {
+            pos,
+            node_type,
+            timer_seconds_duration: duration,
+            timer_seconds_left: duration,
+            to_block: if is_blocker || is_gate {
+                vec![from]
+            } else {
+                vec![]
+            },
+            blockers: inputs,
+            upgrades: NodeUpgrades::default(),
+            rule: NodeRule::default(),
+        });
+        if is_blocker {
+            nodes[from].blockers.push(index);
+        }
+    }
+}
+
+/// Multipliers are never clicked, they boost gains when charged.
+///
+/// Banks holding a deposit can withdraw at any time.
+fn can_click(node: &SavedNode, inherited_blocked: bool) -> bool {
+    if inherited_blocked {
+        return false;
+    }
+    match node.node_type {
+        NodeType::Multiplier { .. } => false,
+        NodeType::Bank { deposit, .. } if !deposit.is_zero() => true,
+        _ => node.is_finished(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::converter::ConverterOutput;
+    use crate::persisted_game::test_saves::save_with;
+
+    fn node(node_type: NodeType, seconds_left: f32) -> SavedNode {
+        crate::persisted_game::test_saves::node(node_type, 10f32, seconds_left)
+    }
+
+    fn simulation_of(nodes: Vec<SavedNode>) -> Simulation {
+        Simulation::from_save(save_with(nodes), Balance::default(), SpawnTable::default())
+    }
+
+    fn play(simulation: &mut Simulation, steps: usize) {
+        for _ in 0..steps {
+            for index in simulation.clickable_nodes() {
+                simulation.click(index);
+            }
+            simulation.step(0.1);
+        }
+    }
+
+    #[test]
+    fn same_seed_and_clicks_give_the_same_save() {
+        let save = default_save_with_seed(42);
+        let new_simulation =
+            || Simulation::from_save(save.clone(), Balance::default(), SpawnTable::default());
+        let (mut a, mut b) = (new_simulation(), new_simulation());
+        play(&mut a, 1000);
+        play(&mut b, 1000);
+        assert!(a.nodes().len() > save.nodes.len(), "nodes should spawn");
+        assert_eq!(
+            serde_json::to_string(&a.to_save()).unwrap(),
+            serde_json::to_string(&b.to_save()).unwrap()
+        );
+    }
+
+    #[test]
+    fn runs_under_minimal_plugins() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(SimulationPlugin)
+            .insert_resource(Simulation::new(
+                0,
+                Balance::default(),
+                SpawnTable::default(),
+            ));
+        let period = app.world.resource::<FixedTime>().period;
+        app.world
+            .resource_mut::<FixedTime>()
+            .tick(period * 3 + Duration::from_millis(1));
+        app.update();
+        let elapsed = app.world.resource::<Simulation>().elapsed_seconds();
+        assert!((elapsed - 3f64 * period.as_secs_f64()).abs() < 1e-6);
+    }
+
+    #[test]
+    fn converted_coins_count_for_the_next_prestige() {
+        let converter = NodeType::Converter {
+            level: 3,
+            output: ConverterOutput::Resource(ResourceKind::Coins),
+        };
+        let mut simulation = simulation_of(vec![node(converter, 0f32)]);
+        simulation
+            .save
+            .resources
+            .set(ResourceKind::Coins, BigNumber::from(1e6));
+        assert!(simulation.click(0));
+        assert_eq!(
+            simulation.prestige().run_coins_earned,
+            BigNumber::from(3u32)
+        );
+    }
+
+    #[test]
+    fn auto_clickers_click_ready_nodes() {
+        let gain = NodeType::Gain {
+            level: 0,
+            resource: ResourceKind::Coins,
+        };
+        let mut auto_clicked = node(gain.clone(), 0f32);
+        auto_clicked.upgrades.auto_click = 1;
+        let mut simulation = simulation_of(vec![auto_clicked, node(gain, 0f32)]);
+        let interval = simulation.nodes()[0]
+            .upgrades
+            .auto_click_interval()
+            .unwrap();
+        for _ in 0..(interval * 10f32) as usize + 1 {
+            simulation.step(0.1);
+        }
+        assert!(matches!(
+            simulation.nodes()[0].node_type,
+            NodeType::Gain { level: 1, .. }
+        ));
+        assert!(matches!(
+            simulation.nodes()[1].node_type,
+            NodeType::Gain { level: 0, .. }
+        ));
+        assert!(simulation.ledger().get(ResourceKind::Coins) > BigNumber::ZERO);
+    }
+
+    #[test]
+    fn rules_click_while_they_hold() {
+        let gain = NodeType::Gain {
+            level: 0,
+            resource: ResourceKind::Coins,
+        };
+        let mut ruled = node(gain, 0f32);
+        ruled.rule.condition = Some(RuleCondition::CoinsBelow(BigNumber::ONE));
+        let mut simulation = simulation_of(vec![ruled]);
+        simulation.step(0.1);
+        assert!(matches!(
+            simulation.nodes()[0].node_type,
+            NodeType::Gain { level: 1, .. }
+        ));
+        // Coins are above the threshold now, even once the timer is done.
+        let mut ruled = simulation.nodes()[0].clone();
+        ruled.timer_seconds_left = 0f32;
+        simulation.save.nodes[0] = ruled;
+        simulation.step(0.1);
+        assert!(matches!(
+            simulation.nodes()[0].node_type,
+            NodeType::Gain { level: 1, .. }
+        ));
+    }
+
+    #[test]
+    fn upgrades_and_rules_follow_the_shop() {
+        let gain = NodeType::Gain {
+            level: 0,
+            resource: ResourceKind::Coins,
+        };
+        let overclock = NodeType::Overclock {
+            level: 0,
+            scope: crate::overclock::OverclockScope::Global,
+        };
+        let multiplier = NodeType::Multiplier { level: 0 };
+        let mut simulation = simulation_of(vec![
+            node(gain, 10f32),
+            node(overclock, 10f32),
+            node(multiplier, 10f32),
+        ]);
+        simulation
+            .save
+            .resources
+            .set(ResourceKind::Coins, BigNumber::from(100u32));
+        assert!(simulation.buy_upgrade(0, Upgrade::AutoClick));
+        assert_eq!(simulation.nodes()[0].upgrades.auto_click, 1);
+        assert_eq!(
+            simulation.ledger().get(ResourceKind::Coins),
+            BigNumber::from(50u32)
+        );
+        assert!(!simulation.buy_upgrade(1, Upgrade::AutoClick));
+        assert!(
+            !simulation.buy_upgrade(0, Upgrade::AutoClick),
+            "too expensive"
+        );
+        // Speed shortens the running timer, keeping the time elapsed.
+        assert!(simulation.buy_upgrade(0, Upgrade::Speed));
+        assert_eq!(simulation.nodes()[0].timer_seconds_duration, 9f32);
+        assert_eq!(simulation.nodes()[0].timer_seconds_left, 9f32);
+        let rule = Some(RuleCondition::AnyGainReady);
+        assert!(simulation.set_rule(0, rule));
+        assert!(!simulation.set_rule(1, rule));
+        assert!(!simulation.set_rule(2, rule));
+    }
+
+    #[test]
+    fn saved_rules_do_not_click_overclocks() {
+        let gain = NodeType::Gain {
+            level: 0,
+            resource: ResourceKind::Coins,
+        };
+        let overclock = NodeType::Overclock {
+            level: 0,
+            scope: crate::overclock::OverclockScope::Global,
+        };
+        let mut ruled = node(overclock, 0f32);
+        ruled.rule.condition = Some(RuleCondition::AnyGainReady);
+        let mut simulation = simulation_of(vec![node(gain, 0f32), ruled]);
+        simulation.step(0.1);
+        assert!(matches!(
+            simulation.nodes()[1].node_type,
+            NodeType::Overclock { level: 0, .. }
+        ));
+        assert!(simulation.to_save().time_buffs.buffs.is_empty());
+    }
+}
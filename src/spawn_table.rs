@@ -0,0 +1,209 @@
+use bevy::{prelude::*, reflect::TypeUuid};
+use rand::distributions::WeightedIndex;
+use rand::prelude::Distribution;
+use rand::Rng;
+use serde::Deserialize;
+
+use crate::big_number::BigNumber;
+use crate::converter::ConverterOutput;
+use crate::gate::GateKind;
+use crate::idle_gains::ResourceKind;
+use crate::json_asset::{JsonAssetPlugin, ValidateAsset};
+use crate::overclock::OverclockScope;
+use crate::persisted_game::NodeType;
+
+/// Which nodes gains spawn, edits are picked up while the game runs on native.
+pub const SPAWN_TABLE_PATH: &str = "game.spawns.json";
+
+pub struct SpawnTablePlugin;
+
+impl Plugin for SpawnTablePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(JsonAssetPlugin::<SpawnTable>::new(
+            SPAWN_TABLE_PATH,
+            &["spawns.json"],
+        ));
+    }
+}
+
+/// Candidates for the node spawned by a gain, read from [`SPAWN_TABLE_PATH`].
+#[derive(Resource, Deserialize, TypeUuid, Clone, Debug)]
+#[uuid = "6a0f2b8e-3d8c-4a5e-9f57-2c1d7e4b9a30"]
+pub struct SpawnTable {
+    pub entries: Vec<SpawnEntry>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct SpawnEntry {
+    /// `None` spawns nothing.
+    pub node: Option<SpawnNode>,
+    /// Relative chance among unlocked entries.
+    pub weight: u32,
+    #[serde(default)]
+    pub unlock: SpawnCondition,
+    /// Stops spawning this node once the map has that many.
+    #[serde(default)]
+    pub max_count: Option<usize>,
+    #[serde(default = "default_initial_level")]
+    pub initial_level: u32,
+}
+
+fn default_initial_level() -> u32 {
+    1
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SpawnNode {
+    Blocker,
+    Save,
+    Gain { resource: ResourceKind },
+    Multiplier,
+    Converter { output: ConverterOutput },
+    Gate { kind: GateKind },
+    Bank,
+    Overclock { scope: OverclockScope },
+}
+
+impl SpawnNode {
+    pub fn of(node_type: &NodeType) -> Self {
+        match node_type {
+            NodeType::Blocker { .. } => SpawnNode::Blocker,
+            NodeType::Save { .. } => SpawnNode::Save,
+            NodeType::Gain { resource, .. } => SpawnNode::Gain {
+                resource: *resource,
+            },
+            NodeType::Multiplier { .. } => SpawnNode::Multiplier,
+            NodeType::Converter { output, .. } => SpawnNode::Converter { output: *output },
+            NodeType::Gate { kind } => SpawnNode::Gate { kind: *kind },
+            NodeType::Bank { .. } => SpawnNode::Bank,
+            NodeType::Overclock { scope, .. } => SpawnNode::Overclock { scope: *scope },
+        }
+    }
+
+    pub fn node_type(self, level: u32) -> NodeType {
+        match self {
+            SpawnNode::Blocker => NodeType::Blocker { is_blocked: true },
+            SpawnNode::Save => NodeType::Save { level },
+            SpawnNode::Gain { resource } => NodeType::Gain { level, resource },
+            SpawnNode::Multiplier => NodeType::Multiplier { level },
+            SpawnNode::Converter { output } => NodeType::Converter { level, output },
+            SpawnNode::Gate { kind } => NodeType::Gate { kind },
+            SpawnNode::Bank => NodeType::Bank {
+                level,
+                deposit: BigNumber::ZERO,
+                rate: 0f64,
+                cycles: 0,
+            },
+            SpawnNode::Overclock { scope } => NodeType::Overclock { level, scope },
+        }
+    }
+}
+
+/// All thresholds must be reached for an entry to be unlocked.
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct SpawnCondition {
+    /// Compared to the coins owned when the node spawns.
+    pub min_currencies: BigNumber,
+    pub min_nodes: usize,
+    pub min_prestige_points: BigNumber,
+    /// Blockers of the node the spawn comes from, gates take them as inputs.
+    pub min_source_blockers: usize,
+}
+
+/// What the spawn conditions are checked against.
+pub struct SpawnContext<'a> {
+    pub currencies_on_click: BigNumber,
+    pub prestige_points: BigNumber,
+    /// Kind of every node on the map.
+    pub nodes: &'a [SpawnNode],
+    /// Blockers of the node the spawn comes from.
+    pub source_blockers: usize,
+}
+
+impl SpawnEntry {
+    /// Weight of this entry in `context`, 0 when locked or at its max count.
+    pub fn weight_in(&self, context: &SpawnContext) -> u32 {
+        let unlock = &self.unlock;
+        let is_unlocked = context.currencies_on_click >= unlock.min_currencies
+            && context.nodes.len() >= unlock.min_nodes
+            && context.prestige_points >= unlock.min_prestige_points
+            && context.source_blockers >= unlock.min_source_blockers;
+        let is_full = match (self.node, self.max_count) {
+            (Some(node), Some(max_count)) => {
+                context.nodes.iter().filter(|n| **n == node).count() >= max_count
+            }
+            _ => false,
+        };
+        if is_unlocked && !is_full {
+            self.weight
+        } else {
+            0
+        }
+    }
+}
+
+/// Weights can't make [`SpawnTable::pick`] fail, an empty table spawns nothing.
+impl ValidateAsset for SpawnTable {}
+
+impl SpawnTable {
+    /// Picks an entry by weight, `None` when nothing is unlocked.
+    pub fn pick(&self, context: &SpawnContext, random: &mut impl Rng) -> Option<&SpawnEntry> {
+        let weights: Vec<u32> = self
+            .entries
+            .iter()
+            .map(|entry| entry.weight_in(context))
+            .collect();
+        // Nothing unlocked, or an empty table.
+        let dist = WeightedIndex::new(&weights).ok()?;
+        Some(&self.entries[dist.sample(random)])
+    }
+}
+
+/// The shipped table, used until the asset is loaded and by the simulation.
+impl Default for SpawnTable {
+    fn default() -> Self {
+        serde_json::from_str(include_str!("../assets/game.spawns.json"))
+            .expect("the shipped spawn table should be valid.")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shipped_table_is_readable() {
+        let table = SpawnTable::default();
+        assert!(!table.entries.is_empty());
+        let context = SpawnContext {
+            currencies_on_click: BigNumber::from(2),
+            prestige_points: BigNumber::ZERO,
+            nodes: &[],
+            source_blockers: 0,
+        };
+        assert!(table.pick(&context, &mut rand::thread_rng()).is_some());
+    }
+
+    #[test]
+    fn gates_need_blockers_to_take_as_inputs() {
+        let table = SpawnTable::default();
+        let nodes = vec![SpawnNode::Blocker; 20];
+        let gate_weight = |source_blockers| {
+            let context = SpawnContext {
+                currencies_on_click: BigNumber::from(1000),
+                prestige_points: BigNumber::ZERO,
+                nodes: &nodes,
+                source_blockers,
+            };
+            table
+                .entries
+                .iter()
+                .filter(|entry| matches!(entry.node, Some(SpawnNode::Gate { .. })))
+                .map(|entry| entry.weight_in(&context))
+                .sum::<u32>()
+        };
+        assert_eq!(gate_weight(0), 0);
+        assert!(gate_weight(1) > 0);
+    }
+}
@@ -2,7 +2,8 @@ use bevy::prelude::*;
 use bevy_picking_highlight::*;
 
 use crate::{
-    new_node::EyeCatcher, picking::HighlightingMaterials, progress::Progress, InheritedBlockStatus,
+    gate::NodeGate, multiplier::NodeMultiplier, new_node::EyeCatcher,
+    picking::HighlightingMaterials, progress::Progress, InheritedBlockStatus,
     NodeManualBlockToggle, SelfBlockStatus,
 };
 
@@ -13,6 +14,8 @@ pub fn update_status_visual(
         &Progress,
         &InheritedBlockStatus,
         Option<&NodeManualBlockToggle>,
+        Option<&NodeMultiplier>,
+        Option<&NodeGate>,
         &mut Highlight<ColorMaterial>,
         &EyeCatcher,
     )>,
@@ -22,6 +25,8 @@ pub fn update_status_visual(
         p,
         inherited_status,
         manual,
+        multiplier,
+        gate,
         mut highlighting,
         eye_catcher)
         // long
@@ -55,7 +60,11 @@ pub fn update_status_visual(
                 *q_visibility.get_mut(eye_catcher.0).unwrap() = Visibility::Hidden;
                 *highlighting = highlighting_mats.node_materials_normal.clone();
             }
-            if manual.is_some() && !self_status.is_blocked {
+            // Charged multipliers and gates don't need a click.
+            if (manual.is_some() && !self_status.is_blocked)
+                || multiplier.is_some()
+                || gate.is_some()
+            {
                 *q_visibility.get_mut(eye_catcher.0).unwrap() = Visibility::Hidden;
             }
             else {
@@ -0,0 +1,552 @@
+use bevy::prelude::*;
+use bevy_mod_picking::prelude::*;
+use bevy_picking_core::events::PointerEvent;
+use serde::{Deserialize, Serialize};
+
+use crate::automation::{NodeRule, RuleCondition};
+use crate::balance::Balance;
+use crate::big_number::BigNumber;
+use crate::idle_gains::{Ledger, ResourceKind};
+use crate::new_node::BaseNode;
+use crate::number_format::format_number;
+use crate::persisted_game::{EventSave, NodeType};
+use crate::progress::{NodeTimer, Progress};
+use crate::save_slots::{SaveSlots, BUTTON_COLOR, BUTTON_HOVERED_COLOR};
+use crate::settings::Settings;
+use crate::{MapAssets, NodeTypeQuery};
+
+/// Each speed level multiplies timer durations by this.
+pub const SPEED_UPGRADE_FACTOR: f32 = 0.9f32;
+/// Interval between clicks of a level 1 auto-clicker.
+const AUTO_CLICK_BASE_SECONDS: f32 = 5f32;
+/// Each further auto-click level multiplies the interval by this.
+const AUTO_CLICK_INTERVAL_FACTOR: f32 = 0.8f32;
+
+const UNAFFORDABLE_COLOR: Color = Color::rgb(0.4, 0.15, 0.15);
+
+pub struct UpgradeShopPlugin;
+
+impl Plugin for UpgradeShopPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SelectedNode>()
+            .add_system(select_node)
+            .add_system(shop_button_react)
+            .add_system(shop_button_hover)
+            .add_system(update_shop_ui.after(select_node).after(shop_button_react))
+            .add_system(update_shop_costs.after(update_shop_ui));
+    }
+}
+
+/// Upgrades bought for a node, persisted with it.
+#[derive(Component, Reflect, Serialize, Deserialize, Clone, Debug, Default)]
+pub struct NodeUpgrades {
+    pub speed: u32,
+    /// Extra resources produced by each gain.
+    pub yield_level: u32,
+    /// Auto-clicker level, 0 without an auto-clicker.
+    #[serde(default)]
+    pub auto_click: u32,
+}
+
+impl NodeUpgrades {
+    pub fn duration_factor(&self) -> f32 {
+        SPEED_UPGRADE_FACTOR.powi(self.speed as i32)
+    }
+
+    pub fn gain_yield(&self) -> BigNumber {
+        BigNumber::from(1 + self.yield_level)
+    }
+
+    /// Seconds between two clicks of the auto-clicker, if bought.
+    pub fn auto_click_interval(&self) -> Option<f32> {
+        (self.auto_click > 0).then(|| {
+            AUTO_CLICK_BASE_SECONDS * AUTO_CLICK_INTERVAL_FACTOR.powi(self.auto_click as i32 - 1)
+        })
+    }
+
+    fn level(&self, upgrade: Upgrade) -> u32 {
+        match upgrade {
+            Upgrade::Speed => self.speed,
+            Upgrade::Yield => self.yield_level,
+            Upgrade::AutoClick => self.auto_click,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ShopNodeKind {
+    Gain,
+    Save,
+    Blocker,
+    Multiplier,
+    Converter,
+    Gate,
+    Bank,
+    Overclock,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Upgrade {
+    Speed,
+    Yield,
+    AutoClick,
+}
+
+impl Upgrade {
+    fn name(self) -> &'static str {
+        match self {
+            Upgrade::Speed => "Speed",
+            Upgrade::Yield => "Yield",
+            Upgrade::AutoClick => "Auto-click",
+        }
+    }
+}
+
+/// Cost in coins of an upgrade: base cost, multiplied by growth for each level already bought.
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct UpgradeCost {
+    pub base: f64,
+    pub growth: f64,
+}
+
+impl UpgradeCost {
+    pub const fn new(base: f64, growth: f64) -> Self {
+        Self { base, growth }
+    }
+
+    /// Upgrades can't be free, and don't get cheaper with levels.
+    pub fn is_valid(&self) -> bool {
+        self.base.is_finite() && self.base > 0f64 && self.growth.is_finite() && self.growth >= 1f64
+    }
+
+    fn at_level(self, level: u32) -> BigNumber {
+        // Growth is applied as a big number, costs can outgrow f64.
+        let mut cost = BigNumber::from(self.base);
+        let growth = BigNumber::from(self.growth);
+        for _ in 0..level {
+            cost = cost * growth;
+        }
+        cost.floor()
+    }
+}
+
+/// Upgrades available for a node type, with their cost from `balance`.
+fn upgrades_for(kind: ShopNodeKind, balance: &Balance) -> Vec<(Upgrade, UpgradeCost)> {
+    match kind {
+        ShopNodeKind::Gain => vec![
+            (Upgrade::Speed, balance.upgrade_gain_speed),
+            (Upgrade::Yield, balance.upgrade_gain_yield),
+            (Upgrade::AutoClick, balance.upgrade_gain_auto_click),
+        ],
+        ShopNodeKind::Save => vec![
+            (Upgrade::Speed, balance.upgrade_save_speed),
+            (Upgrade::AutoClick, balance.upgrade_save_auto_click),
+        ],
+        ShopNodeKind::Blocker => vec![
+            (Upgrade::Speed, balance.upgrade_blocker_speed),
+            (Upgrade::AutoClick, balance.upgrade_blocker_auto_click),
+        ],
+        // Multipliers are never clicked, they only charge faster.
+        ShopNodeKind::Multiplier => vec![(Upgrade::Speed, balance.upgrade_multiplier_speed)],
+        ShopNodeKind::Converter => vec![
+            (Upgrade::Speed, balance.upgrade_converter_speed),
+            (Upgrade::AutoClick, balance.upgrade_converter_auto_click),
+        ],
+        // Gates are switched by hand, an auto-clicker would cycle them all the time.
+        ShopNodeKind::Gate => vec![(Upgrade::Speed, balance.upgrade_gate_speed)],
+        // Faster cycles compound the deposit more often.
+        ShopNodeKind::Bank => vec![(Upgrade::Speed, balance.upgrade_bank_speed)],
+        // Automating overclocks would keep their buff up forever.
+        ShopNodeKind::Overclock => vec![(Upgrade::Speed, balance.upgrade_overclock_speed)],
+    }
+}
+
+/// Buys `upgrade` for a node of `node_type` with coins of `ledger`,
+/// false when it is not offered for the node or too expensive.
+pub fn buy_upgrade(
+    node_type: &NodeType,
+    upgrades: &mut NodeUpgrades,
+    timer: &mut impl NodeTimer,
+    upgrade: Upgrade,
+    ledger: &mut Ledger,
+    balance: &Balance,
+) -> bool {
+    let kind = shop_node_kind(node_type);
+    let Some((_, cost)) = upgrades_for(kind, balance)
+        .into_iter()
+        .find(|(u, _)| *u == upgrade)
+    else {
+        return false;
+    };
+    let cost = cost.at_level(upgrades.level(upgrade));
+    if ledger.get(ResourceKind::Coins) < cost {
+        return false;
+    }
+    ledger.add(ResourceKind::Coins, -cost);
+    match upgrade {
+        Upgrade::Speed => {
+            upgrades.speed += 1;
+            // Following durations get the factor when set, shorten the current one now.
+            timer.scale_duration(SPEED_UPGRADE_FACTOR);
+        }
+        Upgrade::Yield => upgrades.yield_level += 1,
+        Upgrade::AutoClick => upgrades.auto_click += 1,
+    }
+    true
+}
+
+/// Multipliers can't be clicked, rules would cycle gates all the time,
+/// could not withdraw from a bank: its timer only finishes when empty,
+/// and would keep an overclock buff up forever, like auto-clicks did.
+pub fn accepts_rule(node_type: &NodeType) -> bool {
+    !matches!(
+        shop_node_kind(node_type),
+        ShopNodeKind::Multiplier
+            | ShopNodeKind::Gate
+            | ShopNodeKind::Bank
+            | ShopNodeKind::Overclock
+    )
+}
+
+/// Node the shop shows upgrades for, selected with a right click.
+#[derive(Resource, Default)]
+struct SelectedNode(Option<Entity>);
+
+#[derive(Clone, Copy)]
+enum ShopAction {
+    Buy(Upgrade),
+    NextRule,
+    /// Multiplies the coins threshold of the rule.
+    ScaleRule(f64),
+    Close,
+}
+
+#[derive(Component)]
+struct ShopButton(ShopAction);
+
+/// Label of the buy button of an upgrade, updated in place by [`update_shop_costs`].
+#[derive(Component)]
+struct ShopCostText(Upgrade);
+
+#[derive(Component)]
+struct ShopPanelNode;
+
+type ShopQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        &'static mut Progress,
+        &'static mut NodeUpgrades,
+        &'static mut NodeRule,
+        NodeTypeQuery,
+    ),
+    With<BaseNode>,
+>;
+
+fn shop_node_kind(node_type: &NodeType) -> ShopNodeKind {
+    match node_type {
+        NodeType::Gain { .. } => ShopNodeKind::Gain,
+        NodeType::Save { .. } => ShopNodeKind::Save,
+        NodeType::Blocker { .. } => ShopNodeKind::Blocker,
+        NodeType::Multiplier { .. } => ShopNodeKind::Multiplier,
+        NodeType::Converter { .. } => ShopNodeKind::Converter,
+        NodeType::Gate { .. } => ShopNodeKind::Gate,
+        NodeType::Bank { .. } => ShopNodeKind::Bank,
+        NodeType::Overclock { .. } => ShopNodeKind::Overclock,
+    }
+}
+
+fn select_node(
+    mut events: EventReader<PointerEvent<Down>>,
+    mut selected: ResMut<SelectedNode>,
+    q_nodes: Query<(), With<BaseNode>>,
+) {
+    for event in events.iter() {
+        if event.event.button == PointerButton::Secondary && q_nodes.contains(event.target) {
+            selected.0 = Some(event.target);
+        }
+    }
+}
+
+fn shop_button_react(
+    mut selected: ResMut<SelectedNode>,
+    mut ledger: ResMut<Ledger>,
+    balance: Res<Balance>,
+    slots: Res<SaveSlots>,
+    mut event_save: EventWriter<EventSave>,
+    mut q_nodes: ShopQuery,
+    q_buttons: Query<(&Interaction, &ShopButton), Changed<Interaction>>,
+) {
+    for (interaction, button) in q_buttons.iter() {
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+        let upgrade = match button.0 {
+            ShopAction::Buy(upgrade) => upgrade,
+            ShopAction::NextRule => {
+                if let Some(Ok((_, _, mut rule, _))) = selected.0.map(|e| q_nodes.get_mut(e)) {
+                    rule.condition = RuleCondition::next(rule.condition);
+                    event_save.send(EventSave {
+                        slot: slots.current,
+                    });
+                }
+                continue;
+            }
+            ShopAction::ScaleRule(factor) => {
+                if let Some(Ok((_, _, mut rule, _))) = selected.0.map(|e| q_nodes.get_mut(e)) {
+                    rule.condition = rule.condition.map(|condition| condition.scaled(factor));
+                    event_save.send(EventSave {
+                        slot: slots.current,
+                    });
+                }
+                continue;
+            }
+            ShopAction::Close => {
+                selected.0 = None;
+                continue;
+            }
+        };
+        let Some(Ok((mut progress, mut upgrades, _, node_type))) =
+            selected.0.map(|e| q_nodes.get_mut(e))
+        else {
+            continue;
+        };
+        if buy_upgrade(
+            &node_type.node_type(),
+            &mut upgrades,
+            &mut *progress,
+            upgrade,
+            &mut ledger,
+            &balance,
+        ) {
+            event_save.send(EventSave {
+                slot: slots.current,
+            });
+        }
+    }
+}
+
+fn shop_button_hover(
+    mut q_buttons: Query<
+        (&Interaction, &mut BackgroundColor),
+        (Changed<Interaction>, With<ShopButton>),
+    >,
+) {
+    for (interaction, mut color) in q_buttons.iter_mut() {
+        if color.0 == UNAFFORDABLE_COLOR {
+            continue;
+        }
+        *color = match interaction {
+            Interaction::Hovered => BUTTON_HOVERED_COLOR.into(),
+            _ => BUTTON_COLOR.into(),
+        };
+    }
+}
+
+fn buy_label(upgrade: Upgrade, level: u32, cost: BigNumber, settings: &Settings) -> String {
+    format!(
+        "{} {} -> {}: {} coins",
+        upgrade.name(),
+        level,
+        level + 1,
+        format_number(cost, settings.notation)
+    )
+}
+
+fn buy_color(coins: BigNumber, cost: BigNumber, interaction: Interaction) -> Color {
+    if coins < cost {
+        UNAFFORDABLE_COLOR
+    } else if interaction == Interaction::Hovered {
+        BUTTON_HOVERED_COLOR
+    } else {
+        BUTTON_COLOR
+    }
+}
+
+/// Rebuilds the panel when another node is selected or its rule changes,
+/// costs follow coins and levels in [`update_shop_costs`].
+///
+/// The selection is dropped once its node is despawned.
+fn update_shop_ui(
+    mut commands: Commands,
+    map_assets: Res<MapAssets>,
+    mut selected: ResMut<SelectedNode>,
+    ledger: Res<Ledger>,
+    balance: Res<Balance>,
+    settings: Res<Settings>,
+    q_nodes: Query<(&NodeUpgrades, &NodeRule, NodeTypeQuery), With<BaseNode>>,
+    q_changed: Query<(), Changed<NodeRule>>,
+    q_panel: Query<Entity, With<ShopPanelNode>>,
+) {
+    if selected.0.is_some_and(|e| !q_nodes.contains(e)) {
+        selected.0 = None;
+    }
+    let is_rule_changed = selected.0.is_some_and(|e| q_changed.contains(e));
+    if !selected.is_changed() && !balance.is_changed() && !settings.is_changed() && !is_rule_changed
+    {
+        return;
+    }
+    for panel in q_panel.iter() {
+        commands.entity(panel).despawn_recursive();
+    }
+    let Some(Ok((upgrades, rule, node_type))) = selected.0.map(|e| q_nodes.get(e)) else {
+        return;
+    };
+    let node_type = node_type.node_type();
+    let kind = shop_node_kind(&node_type);
+    let coins = ledger.get(ResourceKind::Coins);
+    let text_style = TextStyle {
+        color: Color::WHITE,
+        ..map_assets.text_style.clone()
+    };
+    let button = |parent: &mut ChildBuilder, label: &str, action: ShopAction, color: Color| {
+        parent
+            .spawn((
+                ButtonBundle {
+                    style: Style {
+                        margin: UiRect::all(Val::Px(4.0)),
+                        padding: UiRect::all(Val::Px(6.0)),
+                        ..default()
+                    },
+                    background_color: color.into(),
+                    ..default()
+                },
+                ShopButton(action),
+            ))
+            .with_children(|parent| {
+                let mut text = parent.spawn(TextBundle::from_section(label, text_style.clone()));
+                if let ShopAction::Buy(upgrade) = action {
+                    text.insert(ShopCostText(upgrade));
+                }
+            });
+    };
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        bottom: Val::Px(40.0),
+                        right: Val::Px(15.0),
+                        ..default()
+                    },
+                    flex_direction: FlexDirection::Column,
+                    padding: UiRect::all(Val::Px(10.0)),
+                    ..default()
+                },
+                background_color: Color::rgba(0.1, 0.1, 0.1, 0.9).into(),
+                ..default()
+            },
+            ShopPanelNode,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                format!("{:?} upgrades", kind),
+                text_style.clone(),
+            ));
+            parent.spawn(TextBundle::from_section(
+                if settings.automation_enabled {
+                    "auto-clickers on (C)"
+                } else {
+                    "auto-clickers paused (C)"
+                },
+                text_style.clone(),
+            ));
+            for (upgrade, cost) in upgrades_for(kind, &balance) {
+                let level = upgrades.level(upgrade);
+                let cost = cost.at_level(level);
+                button(
+                    parent,
+                    &buy_label(upgrade, level, cost, &settings),
+                    ShopAction::Buy(upgrade),
+                    buy_color(coins, cost, Interaction::None),
+                );
+            }
+            if accepts_rule(&node_type) {
+                let rule_text = match rule.condition {
+                    None => "no rule".to_string(),
+                    Some(condition) => format!(
+                        "{} {}",
+                        if kind == ShopNodeKind::Blocker {
+                            "unblock while"
+                        } else {
+                            "click when"
+                        },
+                        condition.describe(|coins| format_number(coins, settings.notation))
+                    ),
+                };
+                parent.spawn(TextBundle::from_section(
+                    format!("rule: {}", rule_text),
+                    text_style.clone(),
+                ));
+                button(parent, "Next rule", ShopAction::NextRule, BUTTON_COLOR);
+                if rule.condition.and_then(RuleCondition::threshold).is_some() {
+                    button(
+                        parent,
+                        "Coins x10",
+                        ShopAction::ScaleRule(10f64),
+                        BUTTON_COLOR,
+                    );
+                    button(
+                        parent,
+                        "Coins /10",
+                        ShopAction::ScaleRule(0.1f64),
+                        BUTTON_COLOR,
+                    );
+                }
+            }
+            button(parent, "Close", ShopAction::Close, BUTTON_COLOR);
+        });
+}
+
+/// Updates the buy buttons in place, coins change almost every frame while gains run.
+fn update_shop_costs(
+    selected: Res<SelectedNode>,
+    ledger: Res<Ledger>,
+    balance: Res<Balance>,
+    settings: Res<Settings>,
+    q_nodes: Query<(&NodeUpgrades, NodeTypeQuery), With<BaseNode>>,
+    q_changed: Query<(), Changed<NodeUpgrades>>,
+    mut q_buttons: Query<(&ShopButton, &Interaction, &mut BackgroundColor)>,
+    mut q_texts: Query<(&ShopCostText, &mut Text)>,
+) {
+    let Some(e) = selected.0 else {
+        return;
+    };
+    if !ledger.is_changed() && !q_changed.contains(e) {
+        return;
+    }
+    let Ok((upgrades, node_type)) = q_nodes.get(e) else {
+        return;
+    };
+    let costs = upgrades_for(shop_node_kind(&node_type.node_type()), &balance);
+    let cost_of = |upgrade: Upgrade| {
+        costs
+            .iter()
+            .find(|(offered, _)| *offered == upgrade)
+            .map(|(_, cost)| cost.at_level(upgrades.level(upgrade)))
+    };
+    let coins = ledger.get(ResourceKind::Coins);
+    for (button, interaction, mut color) in q_buttons.iter_mut() {
+        let ShopAction::Buy(upgrade) = button.0 else {
+            continue;
+        };
+        let Some(cost) = cost_of(upgrade) else {
+            continue;
+        };
+        let new_color = buy_color(coins, cost, *interaction);
+        // Only written when different, to keep change detection of the others.
+        if color.0 != new_color {
+            color.0 = new_color;
+        }
+    }
+    for (cost_text, mut text) in q_texts.iter_mut() {
+        let Some(cost) = cost_of(cost_text.0) else {
+            continue;
+        };
+        let label = buy_label(cost_text.0, upgrades.level(cost_text.0), cost, &settings);
+        if text.sections[0].value != label {
+            text.sections[0].value = label;
+        }
+    }
+}